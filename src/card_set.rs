@@ -0,0 +1,296 @@
+use crate::cards::{Card, NUM_CARDS, NUM_RANKS};
+use rand::Rng;
+use std::fmt;
+use std::iter::FromIterator;
+
+const FULL_DECK_BITS: u64 = (1 << NUM_CARDS) - 1;
+// Sixes to aces in each suit
+const SHORT_DECK_SUIT_BITS: u64 = ((1 << NUM_RANKS) - 1) & !0b1111;
+const SHORT_DECK_BITS: u64 =
+    SHORT_DECK_SUIT_BITS * (1 | 1 << NUM_RANKS | 1 << (2 * NUM_RANKS) | 1 << (3 * NUM_RANKS));
+
+/// A set of cards stored as a bitmask, with bit `i` set when the card with index `i` is in the
+/// set. Being a single u64 it is `Copy`, so sets can be passed around and combined without any
+/// heap allocation, which is what we want inside the simulation loop.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub const fn empty() -> CardSet {
+        CardSet(0)
+    }
+
+    pub const fn full_deck() -> CardSet {
+        CardSet(FULL_DECK_BITS)
+    }
+
+    /// The 36-card deck used in short-deck hold'em, with the twos to fives taken out.
+    pub const fn short_deck() -> CardSet {
+        CardSet(SHORT_DECK_BITS)
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Adds a card to the set, returning false if it was already there.
+    pub fn insert(&mut self, card: Card) -> bool {
+        let was_present = self.contains(card);
+        self.0 |= 1 << card.index();
+        !was_present
+    }
+
+    /// Removes a card from the set, returning false if it wasn't there.
+    pub fn remove(&mut self, card: Card) -> bool {
+        let was_present = self.contains(card);
+        self.0 &= !(1 << card.index());
+        was_present
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.0 & (1 << card.index()) != 0
+    }
+
+    pub fn union(&self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+
+    pub fn difference(&self, other: CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+
+    pub fn is_disjoint(&self, other: CardSet) -> bool {
+        self.0 & other.0 == 0
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterates over the cards in the set in index order.
+    pub fn iter(&self) -> CardSetIter {
+        CardSetIter(self.0)
+    }
+
+    /// Iterates over every subset of `k` cards from the set, in lexicographic order of index,
+    /// without allocating.
+    pub fn combinations(&self, k: usize) -> Combinations {
+        let mut cards = [0; NUM_CARDS as usize];
+        for (i, card) in self.iter().enumerate() {
+            cards[i] = card.index();
+        }
+        let mut positions = [0; NUM_CARDS as usize];
+        for (i, position) in positions.iter_mut().enumerate() {
+            *position = i;
+        }
+        Combinations {
+            cards,
+            n: self.count(),
+            k,
+            positions,
+            done: k > self.count(),
+        }
+    }
+
+    /// Removes a uniformly random card from the set and returns it, or None if the set is empty.
+    pub fn deal<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<Card> {
+        if self.is_empty() {
+            return None;
+        }
+        let card = self.nth(rng.gen_range(0..self.count()));
+        self.0 &= !(1 << card.index());
+        Some(card)
+    }
+
+    /// Picks `k` distinct cards uniformly at random from the set, without modifying it.
+    pub fn sample<R: Rng + ?Sized>(&self, k: usize, rng: &mut R) -> CardSet {
+        assert!(k <= self.count(), "Not enough cards to sample from!");
+        let mut remaining = *self;
+        let mut sample = CardSet::empty();
+        for _ in 0..k {
+            sample.0 |= 1 << remaining.deal(rng).unwrap().index();
+        }
+        sample
+    }
+
+    // The card with the n-th lowest index in the set. Caller ensures n < count.
+    fn nth(&self, n: usize) -> Card {
+        let mut bits = self.0;
+        for _ in 0..n {
+            bits &= bits - 1;
+        }
+        Card::from_index(bits.trailing_zeros() as u8)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Combinations {
+    cards: [u8; NUM_CARDS as usize],
+    n: usize,
+    k: usize,
+    positions: [usize; NUM_CARDS as usize],
+    done: bool,
+}
+
+impl Iterator for Combinations {
+    type Item = CardSet;
+
+    fn next(&mut self) -> Option<CardSet> {
+        if self.done {
+            return None;
+        }
+        let (n, k) = (self.n, self.k);
+        let combination = self.positions[..k]
+            .iter()
+            .fold(0, |bits, &position| bits | 1 << self.cards[position]);
+
+        // Advance the rightmost position that still has room to move, and reset the ones after it
+        // to follow on directly.
+        match (0..k).rev().find(|&i| self.positions[i] < n - k + i) {
+            Some(i) => {
+                self.positions[i] += 1;
+                for j in i + 1..k {
+                    self.positions[j] = self.positions[j - 1] + 1;
+                }
+            }
+            None => self.done = true,
+        }
+        Some(CardSet(combination))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CardSetIter(u64);
+
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as u8;
+        self.0 &= self.0 - 1;
+        Some(Card::from_index(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.0.count_ones() as usize;
+        (count, Some(count))
+    }
+}
+
+impl ExactSizeIterator for CardSetIter {}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> CardSetIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> CardSet {
+        let mut set = CardSet::empty();
+        for card in iter {
+            set.insert(card);
+        }
+        set
+    }
+}
+
+impl fmt::Debug for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+    use rand::thread_rng;
+    use std::collections::HashSet;
+
+    fn make_set(cards_str: &str) -> CardSet {
+        parse_cards(cards_str).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn test_insert_remove_contains() {
+        let mut set = CardSet::empty();
+        let ace: Card = "As".parse().unwrap();
+        assert!(set.insert(ace));
+        assert!(!set.insert(ace));
+        assert!(set.contains(ace));
+        assert_eq!(set.count(), 1);
+        assert!(set.remove(ace));
+        assert!(!set.remove(ace));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = make_set("As Kd Qh");
+        let b = make_set("Qh 2c");
+        assert_eq!(a.union(b), make_set("As Kd Qh 2c"));
+        assert_eq!(a.intersection(b), make_set("Qh"));
+        assert_eq!(a.difference(b), make_set("As Kd"));
+        assert!(!a.is_disjoint(b));
+        assert!(a.is_disjoint(make_set("2c 3c")));
+    }
+
+    #[test]
+    fn test_iter_in_index_order() {
+        let cards: Vec<Card> = make_set("As 2c Kd").iter().collect();
+        assert_eq!(cards, parse_cards("2c Kd As").unwrap());
+        assert_eq!(CardSet::full_deck().iter().count(), 52);
+        assert_eq!(CardSet::short_deck().count(), 36);
+        assert!(CardSet::short_deck().iter().all(|card| card.rank() >= 6));
+    }
+
+    #[test]
+    fn test_combinations() {
+        let deck = make_set("2c 3c 4c 5c 6c 7c");
+        let combinations: Vec<CardSet> = deck.combinations(3).collect();
+        assert_eq!(combinations.len(), 20);
+        assert!(combinations.iter().all(|c| c.count() == 3));
+        assert!(combinations.iter().all(|c| c.difference(deck).is_empty()));
+        assert_eq!(combinations.iter().collect::<HashSet<_>>().len(), 20);
+
+        assert_eq!(
+            deck.combinations(0).collect::<Vec<_>>(),
+            vec![CardSet::empty()]
+        );
+        assert_eq!(deck.combinations(6).collect::<Vec<_>>(), vec![deck]);
+        assert_eq!(deck.combinations(7).count(), 0);
+        assert_eq!(CardSet::full_deck().combinations(2).count(), 1326);
+    }
+
+    #[test]
+    fn test_sample_and_deal() {
+        let mut rng = thread_rng();
+        let deck = make_set("2c 3c 4c 5c 6c 7c");
+        for k in 0..=deck.count() {
+            let sample = deck.sample(k, &mut rng);
+            assert_eq!(sample.count(), k);
+            assert_eq!(sample.difference(deck), CardSet::empty());
+        }
+
+        let mut deck = make_set("Ah Kh");
+        let first = deck.deal(&mut rng).unwrap();
+        let second = deck.deal(&mut rng).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(deck.deal(&mut rng), None);
+    }
+}
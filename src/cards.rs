@@ -1,6 +1,9 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error;
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Suit {
     Clubs,
     Hearts,
@@ -8,49 +11,100 @@ pub enum Suit {
     Spades,
 }
 
-#[derive(PartialEq, Eq, Hash)]
-pub struct Card {
-    pub rank: u8,
-    pub suit: Suit,
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Clubs, Suit::Hearts, Suit::Diamonds, Suit::Spades];
+}
+
+pub const NUM_RANKS: u8 = 13;
+pub const NUM_CARDS: u8 = 52;
+
+// A Card is stored as a single index in 0..52, laid out suit by suit: index = 13 * suit +
+// (rank - 2). This keeps it `Copy` and lets a set of cards be a single u64 bitmask (see
+// `CardSet`). Ranks run from 2 to 14 (ace high). Cards are ordered by index, so by suit and
+// then by rank.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Card(u8);
+
+/// The ways in which a single card string such as "As" or "10h" can fail to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardParseError {
+    Empty,
+    InvalidRank(char),
+    MissingSuit,
+    InvalidSuit(char),
+    TrailingCharacters(String),
+}
+
+impl fmt::Display for CardParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CardParseError::Empty => write!(f, "empty card string"),
+            CardParseError::InvalidRank(c) => write!(f, "invalid rank '{}'", c),
+            CardParseError::MissingSuit => write!(f, "missing suit"),
+            CardParseError::InvalidSuit(c) => write!(f, "invalid suit '{}'", c),
+            CardParseError::TrailingCharacters(s) => {
+                write!(f, "unexpected trailing characters \"{}\"", s)
+            }
+        }
+    }
+}
+
+impl Error for CardParseError {}
+
+/// Error from `parse_cards`, recording which token in the input was bad. `position` is the byte
+/// offset of the token within the input string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCardsError {
+    pub position: usize,
+    pub token: String,
+    pub error: CardParseError,
+}
+
+impl fmt::Display for ParseCardsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bad card \"{}\" at position {}: {}",
+            self.token, self.position, self.error
+        )
+    }
+}
+
+impl Error for ParseCardsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
 }
 
 impl Card {
-    pub fn from_str(s: &str) -> Card {
-        let first_char: &str = &s[..1];
-        let second_char: &str = &s[1..2];
-        let rank: u8 = match first_char {
-            "T" => 10,
-            "J" => 11,
-            "Q" => 12,
-            "K" => 13,
-            "A" => 14,
-            _ => first_char.parse::<u8>().unwrap(),
-        };
-        let suit: Suit = match second_char {
-            "c" => Suit::Clubs,
-            "h" => Suit::Hearts,
-            "d" => Suit::Diamonds,
-            "s" => Suit::Spades,
-            _ => panic!("Invalid suit!"),
-        };
-        Card { rank, suit }
+    pub fn new(rank: u8, suit: Suit) -> Card {
+        assert!((2..=14).contains(&rank), "Invalid rank!");
+        Card(suit as u8 * NUM_RANKS + rank - 2)
+    }
+
+    pub fn from_index(index: u8) -> Card {
+        assert!(index < NUM_CARDS, "Invalid card index!");
+        Card(index)
+    }
+
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+
+    pub fn rank(&self) -> u8 {
+        self.0 % NUM_RANKS + 2
+    }
+
+    pub fn suit(&self) -> Suit {
+        Suit::ALL[(self.0 / NUM_RANKS) as usize]
     }
 
     pub fn rank_as_string(&self) -> String {
-        match self.rank {
-            2..=9 => self.rank.to_string(),
-            10 => String::from("T"),
-            11 => String::from("J"),
-            12 => String::from("Q"),
-            13 => String::from("K"),
-            14 => String::from("A"),
-            _ => panic!("Invalid rank!"),
-        }
+        rank_to_char(self.rank()).to_string()
     }
 
-    #[allow(dead_code)]
     pub fn suit_as_string(&self) -> &str {
-        match self.suit {
+        match self.suit() {
             Suit::Clubs => "clubs",
             Suit::Hearts => "hearts",
             Suit::Diamonds => "diamonds",
@@ -59,7 +113,7 @@ impl Card {
     }
 
     pub fn suit_as_char(&self) -> &str {
-        match self.suit {
+        match self.suit() {
             Suit::Clubs => "c",
             Suit::Hearts => "h",
             Suit::Diamonds => "d",
@@ -68,16 +122,7 @@ impl Card {
     }
 
     pub fn create_deck() -> Vec<Card> {
-        let suits = [Suit::Clubs, Suit::Hearts, Suit::Diamonds, Suit::Spades];
-        suits
-            .iter()
-            .flat_map(|suit| {
-                (2..15).map(|rank| Card {
-                    rank: rank,
-                    suit: suit.clone(),
-                })
-            })
-            .collect()
+        (0..NUM_CARDS).map(Card::from_index).collect()
     }
 }
 
@@ -86,3 +131,236 @@ impl fmt::Display for Card {
         write!(f, "{}{}", self.rank_as_string(), self.suit_as_char())
     }
 }
+
+impl fmt::Debug for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for Card {
+    type Err = CardParseError;
+
+    /// Parses a card such as "As", "Td" or "10d". Ranks may be upper or lower case.
+    fn from_str(s: &str) -> Result<Card, CardParseError> {
+        let mut chars = s.chars();
+        let rank: u8 = match chars.next() {
+            None => return Err(CardParseError::Empty),
+            Some('1') => match chars.next() {
+                Some('0') => 10,
+                _ => return Err(CardParseError::InvalidRank('1')),
+            },
+            Some(c) => parse_rank(c).ok_or(CardParseError::InvalidRank(c))?,
+        };
+        let suit: Suit = match chars.next() {
+            None => return Err(CardParseError::MissingSuit),
+            Some(c) => match c.to_ascii_lowercase() {
+                'c' => Suit::Clubs,
+                'h' => Suit::Hearts,
+                'd' => Suit::Diamonds,
+                's' => Suit::Spades,
+                _ => return Err(CardParseError::InvalidSuit(c)),
+            },
+        };
+        let rest = chars.as_str();
+        if !rest.is_empty() {
+            return Err(CardParseError::TrailingCharacters(rest.to_string()));
+        }
+        Ok(Card::new(rank, suit))
+    }
+}
+
+// Cards are serialized as strings such as "As".
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Card, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl TryFrom<&str> for Card {
+    type Error = CardParseError;
+
+    fn try_from(s: &str) -> Result<Card, CardParseError> {
+        s.parse()
+    }
+}
+
+/// The single character used for a rank in card strings, e.g. 'T' for 10 and 'A' for 14.
+pub fn rank_to_char(rank: u8) -> char {
+    match rank {
+        2..=9 => (b'0' + rank) as char,
+        10 => 'T',
+        11 => 'J',
+        12 => 'Q',
+        13 => 'K',
+        14 => 'A',
+        _ => panic!("Invalid rank!"),
+    }
+}
+
+/// The name of a rank, e.g. "Ten" or "Ace".
+pub fn rank_name(rank: u8) -> &'static str {
+    match rank {
+        2 => "Two",
+        3 => "Three",
+        4 => "Four",
+        5 => "Five",
+        6 => "Six",
+        7 => "Seven",
+        8 => "Eight",
+        9 => "Nine",
+        10 => "Ten",
+        11 => "Jack",
+        12 => "Queen",
+        13 => "King",
+        14 => "Ace",
+        _ => panic!("Invalid rank!"),
+    }
+}
+
+/// The plural name of a rank, e.g. "Sixes" or "Aces".
+pub fn rank_name_plural(rank: u8) -> String {
+    match rank {
+        6 => "Sixes".to_string(),
+        _ => format!("{}s", rank_name(rank)),
+    }
+}
+
+/// Parses a single rank character such as 'A', 'k' or '7'. "10" isn't a single character, so
+/// callers that accept it need to handle it themselves.
+pub fn parse_rank(c: char) -> Option<u8> {
+    match c.to_ascii_uppercase() {
+        '2'..='9' => Some(c as u8 - b'0'),
+        'T' => Some(10),
+        'J' => Some(11),
+        'Q' => Some(12),
+        'K' => Some(13),
+        'A' => Some(14),
+        _ => None,
+    }
+}
+
+/// Parses a list of cards such as "AsKd Qh". Cards may be separated by whitespace or written
+/// back to back.
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, ParseCardsError> {
+    let mut cards = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        let position = s.len() - rest.len();
+        let token_len = card_token_len(rest);
+        let token = &rest[..token_len];
+        let card = token.parse().map_err(|error| ParseCardsError {
+            position,
+            token: token.to_string(),
+            error,
+        })?;
+        cards.push(card);
+        rest = rest[token_len..].trim_start();
+    }
+    Ok(cards)
+}
+
+// Length in bytes of the card token at the start of `s`: two characters, or three for a "10"
+// rank, stopping early at whitespace.
+fn card_token_len(s: &str) -> usize {
+    let num_chars = if s.starts_with("10") { 3 } else { 2 };
+    s.char_indices()
+        .take(num_chars)
+        .take_while(|(_, c)| !c.is_whitespace())
+        .map(|(i, c)| i + c.len_utf8())
+        .last()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_card() {
+        let card: Card = "As".parse().unwrap();
+        assert_eq!(card.rank(), 14);
+        assert_eq!(card.suit(), Suit::Spades);
+    }
+
+    #[test]
+    fn test_parse_card_lowercase_rank_and_ten() {
+        let card = Card::try_from("qh").unwrap();
+        assert_eq!(card.rank(), 12);
+        assert_eq!(card.suit(), Suit::Hearts);
+        let ten: Card = "10d".parse().unwrap();
+        assert_eq!(ten, "Td".parse().unwrap());
+    }
+
+    #[test]
+    fn test_card_index_round_trip() {
+        for (index, card) in Card::create_deck().into_iter().enumerate() {
+            assert_eq!(card.index() as usize, index);
+            assert_eq!(Card::new(card.rank(), card.suit()), card);
+        }
+        assert_eq!(Card::new(2, Suit::Clubs).index(), 0);
+        assert_eq!(Card::new(14, Suit::Spades).index(), 51);
+    }
+
+    #[test]
+    fn test_parse_card_errors() {
+        assert_eq!("".parse::<Card>().err(), Some(CardParseError::Empty));
+        assert_eq!(
+            "1s".parse::<Card>().err(),
+            Some(CardParseError::InvalidRank('1'))
+        );
+        assert_eq!(
+            "Xh".parse::<Card>().err(),
+            Some(CardParseError::InvalidRank('X'))
+        );
+        assert_eq!("A".parse::<Card>().err(), Some(CardParseError::MissingSuit));
+        assert_eq!(
+            "Ax".parse::<Card>().err(),
+            Some(CardParseError::InvalidSuit('x'))
+        );
+        assert_eq!(
+            "A♠".parse::<Card>().err(),
+            Some(CardParseError::InvalidSuit('♠'))
+        );
+        assert_eq!(
+            "Ahh".parse::<Card>().err(),
+            Some(CardParseError::TrailingCharacters(String::from("h")))
+        );
+    }
+
+    #[test]
+    fn test_parse_cards() {
+        let cards = parse_cards(" AsKd Qh 10c ").unwrap();
+        let strings: Vec<String> = cards.iter().map(|card| card.to_string()).collect();
+        assert_eq!(strings, vec!["As", "Kd", "Qh", "Tc"]);
+        assert!(parse_cards("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_cards_reports_position() {
+        let error = parse_cards("AsKd Xh").unwrap_err();
+        assert_eq!(error.position, 5);
+        assert_eq!(error.token, "Xh");
+        assert_eq!(error.error, CardParseError::InvalidRank('X'));
+
+        let error = parse_cards("As Q").unwrap_err();
+        assert_eq!(error.position, 3);
+        assert_eq!(error.error, CardParseError::MissingSuit);
+    }
+
+    #[test]
+    fn test_serialize_card() {
+        let card: Card = "Td".parse().unwrap();
+        assert_eq!(serde_json::to_string(&card).unwrap(), r#""Td""#);
+        assert_eq!(serde_json::from_str::<Card>(r#""10d""#).unwrap(), card);
+        assert!(serde_json::from_str::<Card>(r#""Tx""#).is_err());
+    }
+}
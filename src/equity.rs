@@ -1,92 +1,1163 @@
 use crate::game::Game;
 use crate::game::GameSpec;
+use crate::game::GameSpecError;
+use crate::range::Combo;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 const DEFAULT_NUM_SIMULATIONS: u64 = 100000;
-const NUM_THREADS: usize = 16;
 
+// When running until a stopping rule is met, each thread runs up to this many trials between
+// checks.
+const BATCH_SIZE_PER_THREAD: u64 = 10000;
+
+// The z-score for a two-sided 95% confidence interval.
+const Z_95: f64 = 1.959964;
+
+// In `EquityMode::Auto`, situations are enumerated exactly rather than sampled when that takes
+// at most this many hand evaluations per player: one per runout in hold'em, but many more in
+// Omaha, where every allowed combination of hole and board cards is scored.
+const EXACT_ENUMERATION_THRESHOLD: u64 = 2000000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EquityResult {
+    // When players' ranges clash, the equities and percentages are weighted to match the ranges
+    // (see `Game::deal_hole_cards`), while `wins`, `draws`, `losses` and `trials` count the
+    // trials as they were dealt.
     pub equity: f64,
     pub win_percentage: f64,
     pub draw_percentage: f64,
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+    pub trials: u64,
+    // A win is a trial where the player won their part of the pot without sharing it, and a draw
+    // one where they shared some of what they won with other players. So in hi-lo games winning
+    // a clean half counts as a win, while winning one half and sharing the other is a draw.
+    //
+    // In hi-lo games, the parts of `equity` won with high and with low hands, how often the
+    // player won the whole pot (a scoop, which is always a win too) and how often they got just
+    // a quarter of it by splitting one half with one other player. In games played for high
+    // only all of the equity is high equity, in razz it's all low equity, and in neither is
+    // anybody ever quartered.
+    pub high_equity: f64,
+    pub low_equity: f64,
+    pub scoop_percentage: f64,
+    pub quarter_percentage: f64,
+    // The standard error of `equity`, and a 95% confidence interval around it. Both are exact
+    // (zero width) for enumerated results. With fewer than two trials sampled there is no
+    // estimate of the error, and the interval covers every possible equity.
+    pub std_error: Option<f64>,
+    pub confidence_interval: (f64, f64),
+    // For a player given a range, how each hand in the range did when they were dealt it. Empty
+    // for a known hand.
+    pub combos: Vec<(Combo, EquityResult)>,
+    // The seed the simulation was run with, so that it can be reproduced. None for enumerated
+    // results.
+    pub seed: Option<u64>,
+}
+
+/// How to run a simulation: how many threads to split the trials between, a seed to make it
+/// reproducible, and optionally a thread pool to run on instead of spawning threads.
+#[derive(Debug, Clone)]
+pub struct EquityConfig {
+    /// Each thread gets its own random stream, so results depend on the number of threads as
+    /// well as the seed. Defaults to the available parallelism; zero is treated as one.
+    pub num_threads: usize,
+    /// Without a seed a random one is picked, which is reported in the results.
+    pub seed: Option<u64>,
+    pub thread_pool: Option<Arc<ThreadPool>>,
+}
+
+impl Default for EquityConfig {
+    fn default() -> EquityConfig {
+        EquityConfig {
+            num_threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            seed: None,
+            thread_pool: None,
+        }
+    }
+}
+
+/// When to stop a simulation that keeps running until its results are good enough. It stops as
+/// soon as any of the limits that are set is reached, or after the first batch if none are.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct StoppingRule {
+    /// Stop once every player's 95% confidence interval is within this much of their equity,
+    /// e.g. 0.001 for ±0.1% equity. Unless there is another limit, it must be above zero.
+    pub precision: Option<f64>,
+    pub time_budget: Option<Duration>,
+    pub max_simulations: Option<u64>,
+}
+
+/// How to calculate equity: by sampling random runouts, by exhaustively enumerating every
+/// runout, or by picking whichever makes sense for the number of runouts.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EquityMode {
+    MonteCarlo,
+    Exact,
+    #[default]
+    Auto,
+}
+
+// How results were arrived at: by enumerating every runout, which is exact, or by sampling
+// them with the given seed.
+#[derive(Clone, Copy)]
+enum Method {
+    Enumerated,
+    Sampled { seed: u64 },
+}
+
+// What one player won in a showdown: their shares of the pot from the high and low halves,
+// whether they shared either half with anybody else, and whether that was all of the pot or just
+// a quarter.
+#[derive(Clone, Copy, Default)]
+struct Share {
+    high: f64,
+    low: f64,
+    shared: bool,
+    scooped: bool,
+    quartered: bool,
+}
+
+// Running totals for one player (or one hand in a player's range), which can be added up
+// across threads. Each trial is counted with the weight its hands were dealt with (see
+// `Game::deal_hole_cards`), which is 1 unless players' ranges clash. The counts of trials, wins,
+// draws and quarters are of trials as they were dealt, and everything else is weighted.
+#[derive(Clone, Copy, Default)]
+struct Totals {
+    trials: u64,
+    wins: u64,
+    draws: u64,
+    quarters: u64,
+    weight: f64,
+    win_weight: f64,
+    draw_weight: f64,
+    scoop_weight: f64,
+    quarter_weight: f64,
+    equity: f64,
+    high_equity: f64,
+    low_equity: f64,
+    // Sums of the squared weights, and of the squared weights times the equity share and times
+    // its square, for the standard error.
+    weight_squares: f64,
+    equity_weight_squares: f64,
+    equity_squares: f64,
+}
+
+impl Totals {
+    fn record(&mut self, share: Share, weight: f64) {
+        self.trials += 1;
+        self.weight += weight;
+        let total = share.high + share.low;
+        self.equity += weight * total;
+        self.high_equity += weight * share.high;
+        self.low_equity += weight * share.low;
+        let weight_squared = weight * weight;
+        self.weight_squares += weight_squared;
+        self.equity_weight_squares += weight_squared * total;
+        self.equity_squares += weight_squared * total * total;
+        if share.shared {
+            self.draws += 1;
+            self.draw_weight += weight;
+        } else if total > 0. {
+            self.wins += 1;
+            self.win_weight += weight;
+        }
+        if share.scooped {
+            self.scoop_weight += weight;
+        }
+        if share.quartered {
+            self.quarters += 1;
+            self.quarter_weight += weight;
+        }
+    }
+
+    fn add(&mut self, other: &Totals) {
+        self.trials += other.trials;
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.quarters += other.quarters;
+        self.weight += other.weight;
+        self.win_weight += other.win_weight;
+        self.draw_weight += other.draw_weight;
+        self.scoop_weight += other.scoop_weight;
+        self.quarter_weight += other.quarter_weight;
+        self.equity += other.equity;
+        self.high_equity += other.high_equity;
+        self.low_equity += other.low_equity;
+        self.weight_squares += other.weight_squares;
+        self.equity_weight_squares += other.equity_weight_squares;
+        self.equity_squares += other.equity_squares;
+    }
+
+    // The standard error of the weighted mean equity share per trial, if there have been enough
+    // trials to estimate it. When every trial has the same weight this is the usual one from the
+    // sample variance.
+    fn std_error(&self) -> Option<f64> {
+        if self.trials < 2 {
+            return None;
+        }
+        let trials = self.trials as f64;
+        let mean = self.equity / self.weight;
+        let squared_deviations = self.equity_squares - 2. * mean * self.equity_weight_squares
+            + mean * mean * self.weight_squares;
+        let variance = trials / (trials - 1.) * squared_deviations / (self.weight * self.weight);
+        Some(variance.max(0.).sqrt())
+    }
+
+    // Sampled results carry the seed they were simulated with; enumerated results have none,
+    // and no error.
+    fn to_result(self, method: Method, combos: Vec<(Combo, EquityResult)>) -> EquityResult {
+        let weight = self.weight;
+        let equity = self.equity / weight;
+        let (std_error, seed) = match method {
+            Method::Enumerated => (Some(0.), None),
+            Method::Sampled { seed } => (self.std_error(), Some(seed)),
+        };
+        let margin = std_error.map_or(1., |std_error| Z_95 * std_error);
+        EquityResult {
+            equity,
+            win_percentage: 100. * self.win_weight / weight,
+            draw_percentage: 100. * self.draw_weight / weight,
+            wins: self.wins,
+            draws: self.draws,
+            losses: self.trials - self.wins - self.draws,
+            trials: self.trials,
+            high_equity: self.high_equity / weight,
+            low_equity: self.low_equity / weight,
+            scoop_percentage: 100. * self.scoop_weight / weight,
+            quarter_percentage: 100. * self.quarter_weight / weight,
+            std_error,
+            confidence_interval: ((equity - margin).max(0.), (equity + margin).min(1.)),
+            combos,
+            seed,
+        }
+    }
+}
+
+// Totals for one player overall, plus for each hand in their range if they were given one.
+#[derive(Clone)]
+struct PlayerTotals {
+    overall: Totals,
+    combos: Vec<Totals>,
+}
+
+fn new_player_totals(game: &Game, num_players: usize) -> Vec<PlayerTotals> {
+    (0..num_players)
+        .map(|player| PlayerTotals {
+            overall: Totals::default(),
+            combos: vec![Totals::default(); game.range_combos(player).map_or(0, |c| c.len())],
+        })
+        .collect()
+}
+
+// Splits the pot between the players with the best high hand and the players with the best
+// low hand. When both have winners, as in a hi-lo game where somebody has a low, each half of
+// the pot is split separately. Otherwise whichever has winners takes it all.
+fn record_showdown(
+    totals: &mut [PlayerTotals],
+    game: &Game,
+    winning_players: &[usize],
+    low_winning_players: &[usize],
+    weight: f64,
+) {
+    let (high_winners, low_winners) = (winning_players.len(), low_winning_players.len());
+    let split = high_winners > 0 && low_winners > 0;
+    let half = if split { 0.5 } else { 1. };
+    for (player, player_totals) in totals.iter_mut().enumerate() {
+        let won_high = winning_players.contains(&player);
+        let won_low = low_winning_players.contains(&player);
+        let share = Share {
+            high: if won_high {
+                half / high_winners as f64
+            } else {
+                0.
+            },
+            low: if won_low {
+                half / low_winners as f64
+            } else {
+                0.
+            },
+            shared: won_high && high_winners > 1 || won_low && low_winners > 1,
+            scooped: (won_high || won_low)
+                && (high_winners == 0 || won_high && high_winners == 1)
+                && (low_winners == 0 || won_low && low_winners == 1),
+            quartered: split
+                && (won_high && high_winners == 2 && !won_low
+                    || won_low && low_winners == 2 && !won_high),
+        };
+        player_totals.overall.record(share, weight);
+        if let Some(combo) = game.dealt_combo(player) {
+            player_totals.combos[combo].record(share, weight);
+        }
+    }
+}
+
+fn add_player_totals(totals: &mut [PlayerTotals], other: &[PlayerTotals]) {
+    for (player_totals, other_player_totals) in totals.iter_mut().zip(other) {
+        player_totals.overall.add(&other_player_totals.overall);
+        for (combo, other_combo) in player_totals
+            .combos
+            .iter_mut()
+            .zip(&other_player_totals.combos)
+        {
+            combo.add(other_combo);
+        }
+    }
+}
+
+fn to_results(totals: &[PlayerTotals], game: &Game, method: Method) -> Vec<EquityResult> {
+    totals
+        .iter()
+        .enumerate()
+        .map(|(player, player_totals)| {
+            let range_combos = game.range_combos(player).unwrap_or_default();
+            let combos = range_combos
+                .iter()
+                .zip(&player_totals.combos)
+                .filter(|(_, combo_totals)| combo_totals.trials > 0)
+                .map(|(&combo, combo_totals)| (combo, combo_totals.to_result(method, Vec::new())))
+                .collect();
+            player_totals.overall.to_result(method, combos)
+        })
+        .collect()
 }
 
+/// Calculates equity in the given mode. `num_simulations` and `config` are only used when
+/// sampling, but zero simulations is an error in any mode.
+pub fn calculate_equity(
+    game_spec: GameSpec,
+    mode: EquityMode,
+    num_simulations: Option<u64>,
+    config: &EquityConfig,
+) -> Result<Vec<EquityResult>, GameSpecError> {
+    if num_simulations == Some(0) {
+        return Err(GameSpecError::NoSimulations);
+    }
+    let game = Game::from_spec(&game_spec)?;
+    let exact = match mode {
+        EquityMode::MonteCarlo => false,
+        EquityMode::Exact => true,
+        EquityMode::Auto => {
+            !game_spec.has_unknown_hands()
+                && count_runouts(&game) * game_spec.game.variant().hands_per_player()
+                    <= EXACT_ENUMERATION_THRESHOLD
+        }
+    };
+    if exact {
+        if game_spec.has_unknown_hands() {
+            return Err(GameSpecError::CannotEnumerateUnknownHands);
+        }
+        Ok(enumerate_game(game))
+    } else {
+        simulate_game::<StdRng>(&game, num_simulations, config)
+    }
+}
+
+/// The number of distinct ways the rest of the board can be dealt.
+pub fn num_runouts(game_spec: &GameSpec) -> Result<u64, GameSpecError> {
+    Ok(count_runouts(&Game::from_spec(game_spec)?))
+}
+
+fn count_runouts(game: &Game) -> u64 {
+    num_combinations(game.deck().count() as u64, game.num_cards_to_come() as u64)
+}
+
+fn num_combinations(n: u64, k: u64) -> u64 {
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// Calculates exact equities by dealing out every possible runout of the board once. The
+/// results count runouts, so `trials` is the total number of runouts. Only known hands can be
+/// enumerated, not ranges or random hands.
+pub fn enumerate_equity(game_spec: &GameSpec) -> Result<Vec<EquityResult>, GameSpecError> {
+    if game_spec.has_unknown_hands() {
+        return Err(GameSpecError::CannotEnumerateUnknownHands);
+    }
+    Ok(enumerate_game(Game::from_spec(game_spec)?))
+}
+
+fn enumerate_game(mut game: Game) -> Vec<EquityResult> {
+    let num_players = game.num_players();
+    let mut totals = new_player_totals(&game, num_players);
+    let mut winning_players = Vec::with_capacity(num_players);
+    let mut low_winning_players = Vec::with_capacity(num_players);
+
+    for runout in game.deck().combinations(game.num_cards_to_come()) {
+        game.reset();
+        game.deal_runout(runout);
+        game.get_winning_players(&mut winning_players);
+        game.get_winning_low_players(&mut low_winning_players);
+        record_showdown(
+            &mut totals,
+            &game,
+            &winning_players,
+            &low_winning_players,
+            1.,
+        );
+    }
+    to_results(&totals, &game, Method::Enumerated)
+}
+
+/// Simulates exactly `num_simulations` random runouts (and hands, for players whose hands
+/// aren't fully known), split as evenly as possible between the configured threads. Runs with
+/// the same seed and number of threads give identical results.
 pub fn simulate_equity_from_game_spec(
     game_spec: GameSpec,
     num_simulations: Option<u64>,
-) -> Vec<EquityResult> {
-    let num_players = game_spec.hole_cards.len();
+    config: &EquityConfig,
+) -> Result<Vec<EquityResult>, GameSpecError> {
+    simulate_equity_with_rng::<StdRng>(game_spec, num_simulations, config)
+}
+
+/// Like `simulate_equity_from_game_spec`, but using the given type of random number generator.
+pub fn simulate_equity_with_rng<R: Rng + SeedableRng + Send>(
+    game_spec: GameSpec,
+    num_simulations: Option<u64>,
+    config: &EquityConfig,
+) -> Result<Vec<EquityResult>, GameSpecError> {
+    simulate_game::<R>(&Game::from_spec(&game_spec)?, num_simulations, config)
+}
+
+fn simulate_game<R: Rng + SeedableRng + Send>(
+    game: &Game,
+    num_simulations: Option<u64>,
+    config: &EquityConfig,
+) -> Result<Vec<EquityResult>, GameSpecError> {
     let num_simulations = num_simulations.unwrap_or(DEFAULT_NUM_SIMULATIONS);
-    let num_simulations_per_thread = (num_simulations as u32).div_ceil(NUM_THREADS as u32);
-
-    let results = std::thread::scope(|scope| {
-        let mut thread_scopes: Vec<std::thread::ScopedJoinHandle<Vec<EquityResult>>> = Vec::new();
-        for _ in 0..NUM_THREADS {
-            let join_handler = scope.spawn(|| {
-                threaded_simulate_equity_from_game_spec(
-                    &game_spec,
-                    num_simulations_per_thread as u64,
-                )
-            });
-            thread_scopes.push(join_handler);
-        }
-        let mut results: Vec<Vec<EquityResult>> = Vec::new();
-        for join_handler in thread_scopes {
-            let result = join_handler.join().unwrap();
-            results.push(result);
-        }
-        results
-    });
-
-    let mut total_results: Vec<EquityResult> = Vec::new();
-    for player in 0..num_players {
-        let mut sum_equity: f64 = 0.;
-        let mut sum_win_percentage: f64 = 0.;
-        let mut sum_draw_percentage: f64 = 0.;
-        for thread in 0..NUM_THREADS {
-            sum_equity += results[thread][player].equity;
-            sum_win_percentage += results[thread][player].win_percentage;
-            sum_draw_percentage += results[thread][player].draw_percentage;
-        }
-
-        let total_result = EquityResult {
-            equity: sum_equity / NUM_THREADS as f64,
-            win_percentage: sum_win_percentage / NUM_THREADS as f64,
-            draw_percentage: sum_draw_percentage / NUM_THREADS as f64,
+    if num_simulations == 0 {
+        return Err(GameSpecError::NoSimulations);
+    }
+    let seed = config.seed.unwrap_or_else(|| thread_rng().gen());
+    let mut rngs = thread_rngs::<R>(seed, config.num_threads);
+
+    let mut totals = new_player_totals(game, game.num_players());
+    run_batch(
+        game,
+        num_simulations,
+        config.thread_pool.as_deref(),
+        &mut rngs,
+        &mut totals,
+    );
+    Ok(to_results(&totals, game, Method::Sampled { seed }))
+}
+
+/// Keeps simulating in batches across all threads until the stopping rule is met. The number of
+/// trials actually run is reported in each result's `trials`, and never goes over the rule's
+/// `max_simulations`, which can't be zero. A `precision` that can never be reached (zero, below
+/// zero or NaN) is an error unless there is a time budget or maximum number of simulations to
+/// stop at instead. Time budgets aside, runs with the same seed and number of threads give
+/// identical results.
+pub fn simulate_equity_until(
+    game_spec: GameSpec,
+    stopping_rule: StoppingRule,
+    config: &EquityConfig,
+) -> Result<Vec<EquityResult>, GameSpecError> {
+    simulate_equity_until_with_rng::<StdRng>(game_spec, stopping_rule, config)
+}
+
+/// Like `simulate_equity_until`, but using the given type of random number generator.
+pub fn simulate_equity_until_with_rng<R: Rng + SeedableRng + Send>(
+    game_spec: GameSpec,
+    stopping_rule: StoppingRule,
+    config: &EquityConfig,
+) -> Result<Vec<EquityResult>, GameSpecError> {
+    if stopping_rule.max_simulations == Some(0) {
+        return Err(GameSpecError::NoSimulations);
+    }
+    let unreachable = |precision: f64| precision.is_nan() || precision <= 0.;
+    let has_backstop =
+        stopping_rule.time_budget.is_some() || stopping_rule.max_simulations.is_some();
+    if stopping_rule.precision.is_some_and(unreachable) && !has_backstop {
+        return Err(GameSpecError::InvalidPrecision);
+    }
+    let game = Game::from_spec(&game_spec)?;
+    let seed = config.seed.unwrap_or_else(|| thread_rng().gen());
+    let mut rngs = thread_rngs::<R>(seed, config.num_threads);
+    let batch_size = BATCH_SIZE_PER_THREAD * rngs.len() as u64;
+    let start = Instant::now();
+    let mut totals = new_player_totals(&game, game.num_players());
+    loop {
+        let trials = totals[0].overall.trials;
+        let num_simulations = stopping_rule
+            .max_simulations
+            .map_or(batch_size, |max| batch_size.min(max.saturating_sub(trials)));
+        run_batch(
+            &game,
+            num_simulations,
+            config.thread_pool.as_deref(),
+            &mut rngs,
+            &mut totals,
+        );
+        if stopping_rule.is_met(&totals, start.elapsed()) {
+            return Ok(to_results(&totals, &game, Method::Sampled { seed }));
+        }
+    }
+}
+
+// One generator per thread, each seeded from a master generator so that every thread gets its
+// own independent stream but the whole lot is determined by `seed`.
+fn thread_rngs<R: Rng + SeedableRng>(seed: u64, num_threads: usize) -> Vec<R> {
+    let mut master = R::seed_from_u64(seed);
+    (0..num_threads.max(1))
+        .map(|_| R::from_rng(&mut master).expect("Seeding from an RNG never fails"))
+        .collect()
+}
+
+// Splits `num_simulations` trials between `num_threads` threads so that they add up exactly,
+// with the first few threads taking one extra trial each if they don't divide evenly.
+fn split_simulations(num_simulations: u64, num_threads: usize) -> impl Iterator<Item = u64> {
+    let num_threads = num_threads as u64;
+    let (per_thread, remainder) = (num_simulations / num_threads, num_simulations % num_threads);
+    (0..num_threads).map(move |thread| per_thread + u64::from(thread < remainder))
+}
+
+impl StoppingRule {
+    fn is_met(&self, totals: &[PlayerTotals], elapsed: Duration) -> bool {
+        let trials = totals[0].overall.trials;
+        let precise_enough = |precision| {
+            totals.iter().all(|player_totals| {
+                player_totals
+                    .overall
+                    .std_error()
+                    .is_some_and(|std_error| Z_95 * std_error <= precision)
+            })
         };
-        total_results.push(total_result);
+        let no_limits = self.precision.is_none()
+            && self.time_budget.is_none()
+            && self.max_simulations.is_none();
+        no_limits
+            || self.precision.is_some_and(precise_enough)
+            || self.time_budget.is_some_and(|budget| elapsed >= budget)
+            || self.max_simulations.is_some_and(|max| trials >= max)
     }
-    total_results
 }
 
-fn threaded_simulate_equity_from_game_spec(
-    game_spec: &GameSpec,
+// Runs `num_simulations` trials split between one thread per generator, on the given pool or
+// on freshly spawned threads, and adds the raw counts to `totals`. Results are added in thread
+// order so that they don't depend on which thread happens to finish first.
+fn run_batch<R: Rng + Send>(
+    game: &Game,
     num_simulations: u64,
-) -> Vec<EquityResult> {
-    let num_players = game_spec.hole_cards.len();
-    let mut player_win_counts: Vec<u64> = vec![0; num_players];
-    let mut player_draw_counts: Vec<u64> = vec![0; num_players];
-    let mut player_equity_sums: Vec<f64> = vec![0.; num_players];
+    thread_pool: Option<&ThreadPool>,
+    rngs: &mut [R],
+    totals: &mut [PlayerTotals],
+) {
+    let mut results: Vec<Option<Vec<PlayerTotals>>> = (0..rngs.len()).map(|_| None).collect();
+    let num_threads = rngs.len();
+    let work = rngs
+        .iter_mut()
+        .zip(split_simulations(num_simulations, num_threads))
+        .zip(results.iter_mut());
+    match thread_pool {
+        Some(pool) => pool.scope(|scope| {
+            for ((rng, num_simulations), result) in work {
+                scope.spawn(move |_| {
+                    *result = Some(threaded_simulate_equity(game, num_simulations, rng))
+                });
+            }
+        }),
+        None => std::thread::scope(|scope| {
+            for ((rng, num_simulations), result) in work {
+                scope.spawn(move || {
+                    *result = Some(threaded_simulate_equity(game, num_simulations, rng))
+                });
+            }
+        }),
+    }
+
+    for result in results {
+        let thread_totals = result.expect("Every thread has finished");
+        add_player_totals(totals, &thread_totals);
+    }
+}
 
+// Each thread deals from its own copy of the game, which was checked against its spec once
+// before the threads were started.
+fn threaded_simulate_equity<R: Rng>(
+    game: &Game,
+    num_simulations: u64,
+    rng: &mut R,
+) -> Vec<PlayerTotals> {
+    let num_players = game.num_players();
+    let mut game = game.clone();
+    let mut totals = new_player_totals(&game, num_players);
+    let mut winning_players = Vec::with_capacity(num_players);
+    let mut low_winning_players = Vec::with_capacity(num_players);
     for _ in 0..num_simulations {
-        let mut game = Game::from_spec(game_spec);
-        game.deal_down_to_river();
-        let winning_players_and_hands = game.get_winning_players_and_hands();
-        let winners = winning_players_and_hands.len();
-        for (player, _) in winning_players_and_hands {
-            player_equity_sums[player] += 1. / winners as f64;
-            if winners > 1 {
-                player_draw_counts[player] += 1;
-            } else {
-                player_win_counts[player] += 1;
+        game.reset();
+        let weight = game.deal_hole_cards(rng);
+        game.deal_down_to_river(rng);
+        game.get_winning_players(&mut winning_players);
+        game.get_winning_low_players(&mut low_winning_players);
+        record_showdown(
+            &mut totals,
+            &game,
+            &winning_players,
+            &low_winning_players,
+            weight,
+        );
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+    use crate::game::{GameType, Seat};
+    use itertools::Itertools;
+
+    fn make_spec(board: &str, hands: &str) -> GameSpec {
+        GameSpec {
+            board: parse_cards(board).unwrap(),
+            seats: parse_cards(hands)
+                .unwrap()
+                .into_iter()
+                .tuples()
+                .map(|(c1, c2)| Seat::Known(vec![c1, c2]))
+                .collect(),
+            dead_cards: Vec::new(),
+            game: GameType::Holdem,
+        }
+    }
+
+    fn make_range_spec(board: &str, ranges: &[&str]) -> GameSpec {
+        GameSpec {
+            board: parse_cards(board).unwrap(),
+            seats: ranges
+                .iter()
+                .map(|range| Seat::Range(range.parse().unwrap()))
+                .collect(),
+            dead_cards: Vec::new(),
+            game: GameType::Holdem,
+        }
+    }
+
+    fn seeded(seed: u64) -> EquityConfig {
+        EquityConfig {
+            seed: Some(seed),
+            ..EquityConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_enumerate_equity_river_to_come() {
+        // Aces have broadway already. Queens win by filling up or making quads on a K, J, T or
+        // the last queen (10 rivers), and an ace puts broadway on the board for a split (2).
+        let spec = make_spec("Qs Kd Jc Tc", "Qh Qd Ac As");
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!(results[0].trials, 44);
+        assert_eq!((results[0].wins, results[0].draws), (10, 2));
+        assert_eq!((results[1].wins, results[1].draws), (32, 2));
+        assert!((results[0].equity - 11. / 44.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_enumerate_equity_counts_every_runout() {
+        let spec = make_spec("2c 7d 9h", "As Ah Ks Kh");
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!(results[0].trials, 990);
+        assert_eq!(num_runouts(&spec).unwrap(), 990);
+    }
+
+    #[test]
+    fn test_enumerate_equity_complete_board() {
+        let spec = make_spec("2c 7d 9h Tc Js", "As Ah Ks Kh");
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!(results[0].trials, 1);
+        assert_eq!(results[0].wins, 1);
+        assert_eq!(results[1].wins, 0);
+    }
+
+    #[test]
+    fn test_calculate_equity_auto_enumerates_small_spaces() {
+        let spec = make_spec("Qs Kd Jc Tc", "Qh Qd Ac As");
+        let results =
+            calculate_equity(spec, EquityMode::Auto, None, &EquityConfig::default()).unwrap();
+        assert_eq!(results[0].trials, 44);
+    }
+
+    #[test]
+    fn test_range_vs_range_breakdown() {
+        let spec = make_range_spec("Kh Qh 2c 7d", &["KK", "QQ", "AKs"]);
+        let results =
+            simulate_equity_from_game_spec(spec, Some(20000), &EquityConfig::default()).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].equity > results[1].equity);
+
+        // KsKd, KsKc, KdKc; QsQd, QsQc, QdQc; AsKs, AdKd, AcKc; minus the clashes, all dealt
+        assert_eq!(results[0].combos.len(), 3);
+        assert_eq!(results[1].combos.len(), 3);
+        assert_eq!(results[2].combos.len(), 3);
+        for result in &results {
+            let combo_trials: u64 = result.combos.iter().map(|(_, r)| r.trials).sum();
+            assert_eq!(combo_trials, result.trials);
+        }
+    }
+
+    #[test]
+    fn test_clashing_ranges_are_weighted() {
+        // Of the deals that don't share a card, AsAh vs QsQh, KsKh vs AsAd and KsKh vs QsQh are
+        // equally likely, and the first player wins two of them. Dealing them a hand first and
+        // then the second player one that doesn't clash would have them win 3/4 of the time
+        // without the weights.
+        let spec = make_range_spec("2c 3d 7h 8s 9c", &["AsAh, KsKh", "AsAd, QsQh"]);
+        let results = simulate_equity_from_game_spec(spec, Some(100000), &seeded(1)).unwrap();
+        assert!((results[0].equity - 2. / 3.).abs() < 0.01);
+        let (ace_result, king_result) = (&results[0].combos[0].1, &results[0].combos[1].1);
+        assert_eq!(ace_result.equity, 1.);
+        assert!((king_result.equity - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_tight_ranges_at_a_full_table() {
+        // Ten players can only be dealt these hands by splitting each pair between two of them.
+        // By symmetry everyone has the same equity.
+        let spec = make_range_spec("", &["AA, KK, QQ, JJ, TT"; 10]);
+        let results = simulate_equity_from_game_spec(spec, Some(10000), &seeded(1)).unwrap();
+        for result in &results {
+            assert_eq!(result.trials, 10000);
+            assert!((result.equity - 0.1).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn test_range_with_one_hand_matches_known_hand() {
+        let known = enumerate_equity(&make_spec("Qs Kd Jc Tc", "Qh Qd Ac As")).unwrap();
+        let spec = make_range_spec("Qs Kd Jc Tc", &["QhQd", "AcAs"]);
+        let simulated =
+            simulate_equity_from_game_spec(spec, Some(100000), &EquityConfig::default()).unwrap();
+        assert!((simulated[0].equity - known[0].equity).abs() < 0.01);
+        assert_eq!(simulated[0].combos.len(), 1);
+    }
+
+    #[test]
+    fn test_calculate_equity_with_ranges() {
+        let spec = make_range_spec("Qs Kd Jc Tc", &["QQ", "AA"]);
+        assert_eq!(
+            enumerate_equity(&spec).err(),
+            Some(GameSpecError::CannotEnumerateUnknownHands)
+        );
+        let results =
+            calculate_equity(spec, EquityMode::Auto, Some(1000), &EquityConfig::default()).unwrap();
+        assert!(results[0].trials >= 1000);
+    }
+
+    #[test]
+    fn test_confidence_intervals() {
+        let spec = make_spec("Qs Kd Jc Tc", "Qh Qd Ac As");
+        let exact = enumerate_equity(&spec).unwrap();
+        assert_eq!(exact[0].std_error, Some(0.));
+        assert_eq!(
+            exact[0].confidence_interval,
+            (exact[0].equity, exact[0].equity)
+        );
+
+        let simulated =
+            simulate_equity_from_game_spec(spec, Some(100000), &EquityConfig::default()).unwrap();
+        for (result, exact) in simulated.iter().zip(&exact) {
+            let (low, high) = result.confidence_interval;
+            let std_error = result.std_error.unwrap();
+            assert!(std_error > 0. && std_error < 0.01);
+            assert!(low < result.equity && result.equity < high);
+            // Loose enough to (practically) never fail: about 5 standard errors either side
+            assert!((result.equity - exact.equity).abs() < 5. * std_error);
+        }
+    }
+
+    #[test]
+    fn test_simulate_until_precision() {
+        let spec = make_spec("Qs Kd Jc", "Qh Qd Ac As");
+        let stopping_rule = StoppingRule {
+            precision: Some(0.005),
+            ..Default::default()
+        };
+        let config = EquityConfig {
+            num_threads: 4,
+            ..EquityConfig::default()
+        };
+        let results = simulate_equity_until(spec, stopping_rule, &config).unwrap();
+        for result in &results {
+            let (low, high) = result.confidence_interval;
+            assert!(high - low <= 0.01 + 1e-12);
+            assert_eq!(result.trials % (BATCH_SIZE_PER_THREAD * 4), 0);
+        }
+    }
+
+    #[test]
+    fn test_simulate_until_max_simulations() {
+        let spec = make_spec("", "Qh Qd Ac As");
+        let stopping_rule = StoppingRule {
+            precision: Some(0.),
+            time_budget: Some(Duration::from_secs(60)),
+            max_simulations: Some(1),
+        };
+        let results = simulate_equity_until(spec, stopping_rule, &EquityConfig::default()).unwrap();
+        assert_eq!(results[0].trials, 1);
+    }
+
+    #[test]
+    fn test_zero_simulations_is_an_error() {
+        let spec = make_spec("", "Qh Qd Ac As");
+        let config = EquityConfig::default();
+        let error = Err(GameSpecError::NoSimulations);
+        assert_eq!(
+            simulate_equity_from_game_spec(spec.clone(), Some(0), &config),
+            error
+        );
+        let stopping_rule = StoppingRule {
+            max_simulations: Some(0),
+            ..StoppingRule::default()
+        };
+        assert_eq!(
+            simulate_equity_until(spec.clone(), stopping_rule, &config),
+            error
+        );
+        // Even when the simulations wouldn't be used
+        assert_eq!(
+            calculate_equity(spec, EquityMode::Exact, Some(0), &config),
+            error
+        );
+    }
+
+    #[test]
+    fn test_unreachable_precision_needs_another_limit() {
+        let spec = make_spec("", "Qh Qd Ac As");
+        for precision in [0., -0.01, f64::NAN] {
+            let stopping_rule = StoppingRule {
+                precision: Some(precision),
+                ..StoppingRule::default()
+            };
+            assert_eq!(
+                simulate_equity_until(spec.clone(), stopping_rule, &EquityConfig::default()),
+                Err(GameSpecError::InvalidPrecision)
+            );
+        }
+    }
+
+    #[test]
+    fn test_calculate_equity_rejects_invalid_spec() {
+        let spec = make_spec("Qs Kd Jc Tc", "Qs Qd Ac As");
+        assert!(calculate_equity(spec, EquityMode::Exact, None, &EquityConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_simulations_with_the_same_seed_match() {
+        let spec = || make_range_spec("Qs Kd Jc", &["QQ+, AK", "JJ-99, KQs"]);
+        let first = simulate_equity_from_game_spec(spec(), Some(20000), &seeded(42)).unwrap();
+        let second = simulate_equity_from_game_spec(spec(), Some(20000), &seeded(42)).unwrap();
+        let other = simulate_equity_from_game_spec(spec(), Some(20000), &seeded(43)).unwrap();
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!((a.wins, a.draws, a.trials), (b.wins, b.draws, b.trials));
+            assert_eq!(a.equity, b.equity);
+            assert_eq!(a.seed, Some(42));
+        }
+        assert!(first.iter().zip(&other).any(|(a, b)| a.equity != b.equity));
+    }
+
+    #[test]
+    fn test_simulate_until_with_the_same_seed_matches() {
+        let spec = || make_spec("Qs Kd", "Qh Qd Ac As");
+        let stopping_rule = StoppingRule {
+            max_simulations: Some(300000),
+            ..StoppingRule::default()
+        };
+        let first = simulate_equity_until(spec(), stopping_rule, &seeded(7)).unwrap();
+        let second = simulate_equity_until(spec(), stopping_rule, &seeded(7)).unwrap();
+        assert_eq!(first[0].trials, second[0].trials);
+        assert_eq!(first[0].equity, second[0].equity);
+    }
+
+    #[test]
+    fn test_results_report_seed() {
+        let spec = make_spec("Qs Kd Jc", "Qh Qd Ac As");
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!(results[0].seed, None);
+        let results =
+            simulate_equity_from_game_spec(spec, Some(1000), &EquityConfig::default()).unwrap();
+        assert!(results[0].seed.is_some());
+        assert_eq!(results[0].seed, results[1].seed);
+    }
+
+    #[test]
+    fn test_split_simulations() {
+        assert_eq!(split_simulations(10, 3).collect::<Vec<_>>(), vec![4, 3, 3]);
+        assert_eq!(
+            split_simulations(2, 4).collect::<Vec<_>>(),
+            vec![1, 1, 0, 0]
+        );
+        let huge = u32::MAX as u64 * 3 + 7;
+        assert_eq!(split_simulations(huge, 16).sum::<u64>(), huge);
+    }
+
+    #[test]
+    fn test_simulation_runs_exactly_the_requested_trials() {
+        let config = EquityConfig {
+            num_threads: 3,
+            ..EquityConfig::default()
+        };
+        let spec = make_spec("Qs Kd Jc", "Qh Qd Ac As");
+        let results = simulate_equity_from_game_spec(spec, Some(10007), &config).unwrap();
+        for result in &results {
+            assert_eq!(result.trials, 10007);
+            assert_eq!(result.wins + result.draws + result.losses, 10007);
+        }
+
+        let stopping_rule = StoppingRule {
+            max_simulations: Some(12345),
+            ..StoppingRule::default()
+        };
+        let spec = make_spec("Qs Kd Jc", "Qh Qd Ac As");
+        let results = simulate_equity_until(spec, stopping_rule, &config).unwrap();
+        assert_eq!(results[0].trials, 12345);
+    }
+
+    #[test]
+    fn test_simulation_on_thread_pool_matches_spawned_threads() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let spawned = EquityConfig {
+            num_threads: 5,
+            seed: Some(99),
+            thread_pool: None,
+        };
+        let pooled = EquityConfig {
+            thread_pool: Some(Arc::new(pool)),
+            ..spawned.clone()
+        };
+        let spec = || make_range_spec("Qs Kd Jc", &["QQ+, AK", "JJ-99, KQs"]);
+        let first = simulate_equity_from_game_spec(spec(), Some(20000), &spawned).unwrap();
+        let second = simulate_equity_from_game_spec(spec(), Some(20000), &pooled).unwrap();
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!((a.wins, a.draws, a.trials), (b.wins, b.draws, b.trials));
+            assert_eq!(a.equity, b.equity);
+        }
+    }
+
+    #[test]
+    fn test_dead_cards_in_enumeration_and_simulation() {
+        // With the last queen dead the queens lose one of their ten winning rivers
+        let mut spec = make_spec("Qs Kd Jc Tc", "Qh Qd Ac As");
+        spec.dead_cards = parse_cards("Qc").unwrap();
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!(results[0].trials, 43);
+        assert_eq!((results[0].wins, results[0].draws), (9, 2));
+
+        let simulated = simulate_equity_from_game_spec(spec, Some(100000), &seeded(1)).unwrap();
+        for (result, exact) in simulated.iter().zip(&results) {
+            assert!((result.equity - exact.equity).abs() < 5. * result.std_error.unwrap());
+        }
+
+        // A dead card blocks hands from a range as well as runouts
+        let mut spec = make_range_spec("Qs Kd Jc", &["AA, KK", "QQ"]);
+        spec.dead_cards = parse_cards("As Ah Ad").unwrap();
+        let results = simulate_equity_from_game_spec(spec, Some(10000), &seeded(1)).unwrap();
+        assert!(results[0].combos.iter().all(|((c1, _), _)| c1.rank() == 13));
+    }
+
+    #[test]
+    fn test_equity_against_random_hands() {
+        // Aces win about 85% of the time against one random hand and about 49% against five
+        let spec = GameSpec::against_random(Vec::new(), "AhAs".parse().unwrap(), 1);
+        let results = simulate_equity_from_game_spec(spec, Some(100000), &seeded(1)).unwrap();
+        assert!((results[0].equity - 0.852).abs() < 0.01);
+        let spec = GameSpec::against_random(Vec::new(), "AhAs".parse().unwrap(), 5);
+        let results = simulate_equity_from_game_spec(spec, Some(100000), &seeded(1)).unwrap();
+        assert!((results[0].equity - 0.49).abs() < 0.02);
+        assert!((results[1].equity - 0.102).abs() < 0.01);
+
+        let spec = GameSpec::against_random(Vec::new(), "Ah".parse().unwrap(), 1);
+        assert_eq!(
+            enumerate_equity(&spec).err(),
+            Some(GameSpecError::CannotEnumerateUnknownHands)
+        );
+        let results = calculate_equity(spec, EquityMode::Auto, Some(100000), &seeded(1)).unwrap();
+        assert!(results[0].seed.is_some());
+        assert!(results[0].equity > 0.55);
+    }
+
+    fn make_game_spec(game: GameType, board: &str, hands: &[&str]) -> GameSpec {
+        GameSpec {
+            board: parse_cards(board).unwrap(),
+            seats: hands.iter().map(|hand| hand.parse().unwrap()).collect(),
+            dead_cards: Vec::new(),
+            game,
+        }
+    }
+
+    #[test]
+    fn test_omaha_enumeration_matches_simulation() {
+        let spec = make_game_spec(GameType::Omaha, "Qs Kd 7c", &["AhAd2c3d", "KsKh7s8s"]);
+        let exact = enumerate_equity(&spec).unwrap();
+        // 52 - 3 board cards - 8 hole cards = 41 left for the turn and river
+        assert_eq!(exact[0].trials, 41 * 40 / 2);
+        let simulated = simulate_equity_from_game_spec(spec, Some(20000), &seeded(8)).unwrap();
+        for (exact, simulated) in exact.iter().zip(&simulated) {
+            assert!((exact.equity - simulated.equity).abs() < 4. * simulated.std_error.unwrap());
+        }
+    }
+
+    #[test]
+    fn test_omaha_equity_with_random_opponent() {
+        let spec = make_game_spec(GameType::Omaha6, "", &["AhAd2c3d", "random"]);
+        let results = calculate_equity(spec, EquityMode::Auto, Some(2000), &seeded(3)).unwrap();
+        assert_eq!(results[0].trials, 2000);
+        assert!((results[0].equity + results[1].equity - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_auto_accounts_for_omaha_hands_per_player() {
+        // 44 rivers is few enough to enumerate even with 60 hands to score per player, but the
+        // 40,000 flops after a preflop all-in in six-card Omaha are not.
+        let spec = make_game_spec(GameType::Omaha, "Qs Kd Jc Tc", &["AhAd2c3d", "KsKh7s8s"]);
+        let results = calculate_equity(spec, EquityMode::Auto, None, &seeded(1)).unwrap();
+        assert_eq!(results[0].seed, None);
+        let spec = make_game_spec(GameType::Omaha6, "", &["AhAd2c3d5h6h", "KsKh7s8s9sTs"]);
+        let results = calculate_equity(spec, EquityMode::Auto, Some(100), &seeded(1)).unwrap();
+        assert_eq!(results[0].seed, Some(1));
+        assert_eq!(results[0].trials, 100);
+    }
+
+    #[test]
+    fn test_short_deck_flush_beats_full_house() {
+        let mut spec = make_spec("Ah Kh 6h Ks 6c", "Qh 7h Kd 8c");
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!((results[0].wins, results[1].wins), (0, 1));
+        spec.game = GameType::ShortDeck;
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!((results[0].wins, results[1].wins), (1, 0));
+    }
+
+    #[test]
+    fn test_short_deck_enumeration_matches_simulation() {
+        let mut spec = make_spec("6c 7d 8h Ks", "As 9c Kd Kh");
+        spec.game = GameType::ShortDeck;
+        let exact = enumerate_equity(&spec).unwrap();
+        // 36 - 4 board cards - 4 hole cards
+        assert_eq!(exact[0].trials, 28);
+        // The ace-nine already has the A-6-7-8-9 straight, and only loses when the board pairs
+        // to fill up the kings: the last king and three each of sixes, sevens and eights
+        assert_eq!(exact[0].wins, 18);
+        let simulated = simulate_equity_from_game_spec(spec, Some(20000), &seeded(4)).unwrap();
+        assert!(
+            (exact[0].equity - simulated[0].equity).abs() < 4. * simulated[0].std_error.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hi_lo_split_pot() {
+        // The ace-trey makes a seven low but loses high to the trip kings
+        let mut spec = make_game_spec(
+            GameType::OmahaHiLo,
+            "2c 4d 7h Kc Ks",
+            &["Ac3dQhQd", "Kd8s9hJs"],
+        );
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!((results[0].high_equity, results[0].low_equity), (0., 0.5));
+        assert_eq!((results[1].high_equity, results[1].low_equity), (0.5, 0.));
+        // Each wins a clean half, which is a win but not a scoop
+        assert_eq!((results[0].wins, results[1].wins), (1, 1));
+        assert_eq!((results[0].draws, results[1].draws), (0, 0));
+        assert_eq!(results[1].win_percentage, 100.);
+        assert_eq!(results[1].scoop_percentage, 0.);
+
+        // Two players with the same low split it, and the one who loses high is quartered
+        spec.seats[1] = "As3h9c9d".parse().unwrap();
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!((results[0].equity, results[1].equity), (0.75, 0.25));
+        assert_eq!(results[0].quarter_percentage, 0.);
+        assert_eq!(results[1].quarter_percentage, 100.);
+        // Both share the low half, so neither won outright
+        assert_eq!((results[0].draws, results[1].draws), (1, 1));
+        assert_eq!((results[0].wins, results[1].wins), (0, 0));
+        assert_eq!(results[0].scoop_percentage, 0.);
+
+        // Without a qualifying low the high hand scoops the whole pot
+        let spec = make_game_spec(
+            GameType::OmahaHiLo,
+            "9c Td Jh Kc Ks",
+            &["Ac3dQhQd", "Kd8s9hJs"],
+        );
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!((results[1].equity, results[1].high_equity), (1., 1.));
+        assert_eq!((results[1].wins, results[1].scoop_percentage), (1, 100.));
+    }
+
+    #[test]
+    fn test_hi_lo_enumeration_matches_simulation() {
+        let spec = make_game_spec(
+            GameType::OmahaHiLo,
+            "2c 7d Kh",
+            &["Ah3d4sKs", "As2h8c8d", "QcJcTh9h"],
+        );
+        let exact = enumerate_equity(&spec).unwrap();
+        let simulated = simulate_equity_from_game_spec(spec, Some(20000), &seeded(9)).unwrap();
+        for (exact, simulated) in exact.iter().zip(&simulated) {
+            assert!((exact.equity - simulated.equity).abs() < 4. * simulated.std_error.unwrap());
+            for result in [exact, simulated] {
+                let parts = result.high_equity + result.low_equity;
+                assert!((result.equity - parts).abs() < 1e-9);
             }
         }
+        // The high-only hand can never win any of the low half
+        assert_eq!(exact[2].low_equity, 0.);
+        let total_equity: f64 = exact.iter().map(|result| result.equity).sum();
+        assert!((total_equity - 1.).abs() < 1e-9);
     }
 
-    (0..num_players)
-        .map(|p| EquityResult {
-            equity: player_equity_sums[p] / num_simulations as f64,
-            win_percentage: 100. * player_win_counts[p] as f64 / num_simulations as f64,
-            draw_percentage: 100. * player_draw_counts[p] as f64 / num_simulations as f64,
-        })
-        .collect()
+    #[test]
+    fn test_stud_games_split_pots() {
+        // A wheel straight against four sixes: the sixes win high, and the wheel wins low
+        let mut spec = make_game_spec(GameType::Stud, "", &["As2d3c4h5sKdKh", "6c6d6h6s7c8c9c"]);
+        let mut equities = Vec::new();
+        for game in [GameType::Stud, GameType::StudHiLo, GameType::Razz] {
+            spec.game = game;
+            let results = enumerate_equity(&spec).unwrap();
+            assert_eq!(results[0].trials, 1);
+            equities.push((results[0].equity, results[1].equity));
+        }
+        assert_eq!(equities, [(0., 1.), (0.5, 0.5), (1., 0.)]);
+
+        spec.game = GameType::Razz;
+        let results = enumerate_equity(&spec).unwrap();
+        assert_eq!((results[0].low_equity, results[0].high_equity), (1., 0.));
+        assert_eq!(results[0].scoop_percentage, 100.);
+    }
+
+    #[test]
+    fn test_stud_equity_with_unknown_cards() {
+        // Three small cards are a big favourite in razz against three big ones, and the dead
+        // cards are never dealt
+        let mut spec = make_game_spec(GameType::Razz, "", &["As2d3c", "KdQhJc"]);
+        spec.dead_cards = parse_cards("4s 4d 5h").unwrap();
+        let results =
+            calculate_equity(spec.clone(), EquityMode::Auto, Some(5000), &seeded(3)).unwrap();
+        assert_eq!(results[0].seed, Some(3));
+        assert!(results[0].equity > 0.85);
+        assert_eq!(results[0].equity, results[0].low_equity);
+
+        // Splitting the known cards into down and up cards doesn't change the deal
+        let mut split_spec = make_game_spec(GameType::Razz, "", &["As2d/3c", "/KdQhJc"]);
+        split_spec.dead_cards = spec.dead_cards.clone();
+        let split_results =
+            calculate_equity(split_spec, EquityMode::Auto, Some(5000), &seeded(3)).unwrap();
+        assert_eq!(split_results, results);
+
+        spec.game = GameType::StudHiLo;
+        let results = simulate_equity_from_game_spec(spec, Some(5000), &seeded(3)).unwrap();
+        for result in &results {
+            let parts = result.high_equity + result.low_equity;
+            assert!((result.equity - parts).abs() < 1e-9);
+        }
+        assert!(results[0].low_equity > results[1].low_equity);
+    }
 }
@@ -0,0 +1,753 @@
+use crate::card_set::CardSet;
+use crate::cards::{Card, Suit, NUM_RANKS};
+use crate::hands::{HandRanking, HandType};
+use itertools::Itertools;
+use std::sync::OnceLock;
+
+// Fast hand evaluation for the simulation loop.
+//
+// `Hand::new` scores a single 5-card hand by sorting and grouping the cards, and the best hand
+// out of 7 cards is found by trying all 21 combinations. That is far too slow when we want to
+// run millions of trials, so here we score a set of 5, 6 or 7 cards directly from its bitmask.
+//
+// Each suit of a `CardSet` is a 13-bit mask of ranks (bit 0 = deuce, bit 12 = ace). Flushes come
+// straight from those masks, and intersecting them tells us which ranks appear two, three or four
+// times. Lookup tables indexed by a 13-bit rank mask, built lazily on first use, give the
+// straight (if any) and the top ranks of each mask, so no sorting is needed.
+//
+// The result is a `HandStrength` that packs the same (level, score) pair that `Hand` uses, so a
+// strength from here compares exactly like the corresponding `Hand`. Short-deck hands are
+// scored the same way, with their own lowest straight and ordering of the hand types.
+
+const SCORE_BITS: u32 = 20;
+const LEVEL_BITS: u32 = 4;
+const RANK_MASK: u64 = (1 << NUM_RANKS) - 1;
+const NUM_RANK_MASKS: usize = 1 << NUM_RANKS;
+const WHEEL: u16 = 0b1_0000_0000_1111;
+const SHORT_DECK_WHEEL: u16 = 0b1_0000_1111_0000;
+
+/// The number of distinct five-card hand values. Hands that differ only in suits (other than
+/// being a flush) are worth the same, so the 2,598,960 five-card hands fall into this many classes.
+pub const NUM_HAND_CLASSES: u16 = 7462;
+
+/// The number of five-card hands that can be dealt from a 52-card deck.
+pub const NUM_FIVE_CARD_HANDS: u32 = 2_598_960;
+
+/// The strength of a hand as a single integer which can be compared directly: a higher strength
+/// beats a lower one and equal strengths draw. Strengths from different rankings shouldn't be
+/// compared.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HandStrength(u32);
+
+impl HandStrength {
+    // The strength of a hand under the given ranking, which decides how its level compares to
+    // the others. The level must be a valid hand type level.
+    pub(crate) fn ranked(ranking: HandRanking, level: u8, score: u64) -> HandStrength {
+        let order = ranking.order(HandType::from_level(level));
+        HandStrength::pack(ranking, order, level, score)
+    }
+
+    // From the most significant bits down: the ranking, where the hand type comes in it, its
+    // level and the score. The ranking is the same for all the strengths that get compared, so
+    // it only matters for `class_rank` and `percentile`.
+    fn pack(ranking: HandRanking, order: u8, level: u8, score: u64) -> HandStrength {
+        debug_assert!(score < 1 << SCORE_BITS);
+        let ranking_and_order = ((ranking_index(ranking) as u32) << LEVEL_BITS) | order as u32;
+        let ranking_order_and_level = (ranking_and_order << LEVEL_BITS) | level as u32;
+        HandStrength((ranking_order_and_level << SCORE_BITS) | score as u32)
+    }
+
+    /// The ranking this hand was scored under.
+    pub fn ranking(&self) -> HandRanking {
+        RANKINGS[(self.0 >> (SCORE_BITS + 2 * LEVEL_BITS)) as usize]
+    }
+
+    pub fn level(&self) -> u8 {
+        ((self.0 >> SCORE_BITS) & ((1 << LEVEL_BITS) - 1)) as u8
+    }
+
+    pub fn hand_type(&self) -> HandType {
+        HandType::from_level(self.level())
+    }
+
+    /// Where this hand value ranks among all the distinct values under its ranking, counting from
+    /// the best. For standard hands 1 is a royal flush and `NUM_HAND_CLASSES` (7462) is
+    /// 7-5-4-3-2 offsuit. Short-deck hands are ranked among the values that can be dealt from the
+    /// short deck, in the order of their own ranking. Unlike the strength itself, this is
+    /// comparable across hand types and meaningful on its own.
+    pub fn class_rank(&self) -> u16 {
+        let classes = hand_classes(self.ranking());
+        (classes.strengths.len() - classes.index(*self)) as u16
+    }
+
+    /// The percentage of all five-card hands from the deck of this hand's ranking that it beats,
+    /// from 0 for the weakest hand up to just under 100 for a royal flush.
+    pub fn percentile(&self) -> f64 {
+        let classes = hand_classes(self.ranking());
+        let beaten = classes.hands_below[classes.index(*self)];
+        100. * beaten as f64 / classes.num_hands as f64
+    }
+}
+
+const RANKINGS: [HandRanking; 3] = [
+    HandRanking::Standard,
+    HandRanking::ShortDeck,
+    HandRanking::ShortDeckTripsBeatStraight,
+];
+
+fn ranking_index(ranking: HandRanking) -> usize {
+    match ranking {
+        HandRanking::Standard => 0,
+        HandRanking::ShortDeck => 1,
+        HandRanking::ShortDeckTripsBeatStraight => 2,
+    }
+}
+
+// Every distinct hand value under one ranking, weakest first, with the number of five-card hands
+// weaker than each, and the number of five-card hands in the deck.
+struct HandClasses {
+    strengths: Vec<HandStrength>,
+    hands_below: Vec<u32>,
+    num_hands: u32,
+}
+
+impl HandClasses {
+    fn index(&self, strength: HandStrength) -> usize {
+        self.strengths
+            .binary_search(&strength)
+            .expect("Every strength is one of the hand classes")
+    }
+}
+
+fn hand_classes(ranking: HandRanking) -> &'static HandClasses {
+    static HAND_CLASSES: [OnceLock<HandClasses>; RANKINGS.len()] =
+        [const { OnceLock::new() }; RANKINGS.len()];
+    HAND_CLASSES[ranking_index(ranking)].get_or_init(|| {
+        // One hand for each way of choosing five ranks from the deck, using at most four of any
+        // rank. Repeated ranks take different suits; five different ranks make both a flush and
+        // a non-flush.
+        let lowest_rank = if ranking.is_short_deck() { 6 } else { 2 };
+        let mut strengths = Vec::new();
+        for ranks in (lowest_rank..=14u8).combinations_with_replacement(5) {
+            let counts: Vec<usize> = ranks.iter().dedup_with_count().map(|(n, _)| n).collect();
+            if counts.iter().any(|&n| n > 4) {
+                continue;
+            }
+            let hand = |suit_of: &dyn Fn(usize) -> usize| -> CardSet {
+                ranks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &rank)| Card::new(rank, Suit::ALL[suit_of(i)]))
+                    .collect()
+            };
+            if counts.len() == 5 {
+                strengths.push(evaluate_with_ranking(hand(&|_| 0), ranking));
+                strengths.push(evaluate_with_ranking(
+                    hand(&|i| usize::from(i == 0)),
+                    ranking,
+                ));
+            } else {
+                let suit_of = |i| ranks[..i].iter().filter(|&&rank| rank == ranks[i]).count();
+                strengths.push(evaluate_with_ranking(hand(&suit_of), ranking));
+            }
+        }
+        strengths.sort();
+
+        let mut num_hands = 0;
+        let hands_below = strengths
+            .iter()
+            .map(|strength| {
+                let below = num_hands;
+                num_hands += hands_in_class(strength.hand_type());
+                below
+            })
+            .collect();
+        HandClasses {
+            strengths,
+            hands_below,
+            num_hands,
+        }
+    })
+}
+
+// How many of the five-card hands share any one hand value of the given type. Every value of a
+// type can be made the same number of ways: a flush or straight flush once in each suit, a
+// straight or high card in any suits except all the same one, and repeated ranks by choosing
+// their suits.
+fn hands_in_class(hand_type: HandType) -> u32 {
+    match hand_type {
+        HandType::StraightFlush | HandType::Quads | HandType::Flush => 4,
+        HandType::FullHouse => 4 * 6,
+        HandType::Straight | HandType::HighCard => 4 * 4 * 4 * 4 * 4 - 4,
+        HandType::ThreeOfAKind => 4 * 4 * 4,
+        HandType::TwoPair => 6 * 6 * 4,
+        HandType::Pair => 6 * 4 * 4 * 4,
+    }
+}
+
+struct Tables {
+    // The low rank of the highest straight in the mask (1 for the wheel), or 0 if none.
+    straight_low: Vec<u8>,
+    // The top five ranks in the mask, highest first, packed four bits each like `Hand` scores.
+    // Masks with fewer than five ranks are padded with zeros at the bottom.
+    top_five: Vec<u32>,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let straight_low = (0..NUM_RANK_MASKS)
+            .map(|mask| find_straight(mask as u16))
+            .collect();
+        let top_five = (0..NUM_RANK_MASKS)
+            .map(|mask| pack_top_five(mask as u16))
+            .collect();
+        Tables {
+            straight_low,
+            top_five,
+        }
+    })
+}
+
+fn find_straight(mask: u16) -> u8 {
+    let runs = mask & (mask << 1) & (mask << 2) & (mask << 3) & (mask << 4);
+    if runs != 0 {
+        // bit i of `runs` is set when ranks i-4..=i are all present; i.e. rank i+2 is the top
+        let top_bit = 15 - runs.leading_zeros() as u8;
+        return top_bit - 2;
+    }
+    if mask & WHEEL == WHEEL {
+        return 1;
+    }
+    0
+}
+
+fn pack_top_five(mask: u16) -> u32 {
+    let mut remaining = mask;
+    let mut packed = 0;
+    for _ in 0..5 {
+        packed <<= 4;
+        if remaining != 0 {
+            let top_bit = 15 - remaining.leading_zeros();
+            packed |= top_bit + 2;
+            remaining &= !(1 << top_bit);
+        }
+    }
+    packed
+}
+
+// The top `n` ranks of a mask, packed. Assumes the mask has at least `n` ranks.
+fn top(tables: &Tables, mask: u64, n: u32) -> u64 {
+    (tables.top_five[mask as usize] >> (4 * (5 - n))) as u64
+}
+
+fn highest_bit(mask: u64) -> u64 {
+    1 << (63 - mask.leading_zeros())
+}
+
+fn rank_of_bit(bit: u64) -> u64 {
+    bit.trailing_zeros() as u64 + 2
+}
+
+// The low rank of the highest straight in a rank mask, or 0 if there is none. In short deck the
+// lowest straight is A-6-7-8-9, with the ace playing as a five.
+fn straight_low(tables: &Tables, mask: u64, ranking: HandRanking) -> u64 {
+    match tables.straight_low[mask as usize] {
+        0 if ranking.is_short_deck() && mask as u16 & SHORT_DECK_WHEEL == SHORT_DECK_WHEEL => 5,
+        low => low as u64,
+    }
+}
+
+/// Scores the best five-card hand that can be made from a set of 5, 6 or 7 cards.
+///
+/// # Panics
+///
+/// Panics if the set doesn't hold 5, 6 or 7 cards.
+pub fn evaluate(cards: CardSet) -> HandStrength {
+    evaluate_with_ranking(cards, HandRanking::Standard)
+}
+
+/// Like `evaluate`, but ranking the hands by the given rules.
+///
+/// # Panics
+///
+/// Panics if the set doesn't hold 5, 6 or 7 cards.
+pub fn evaluate_with_ranking(cards: CardSet, ranking: HandRanking) -> HandStrength {
+    assert!(
+        (5..=7).contains(&cards.count()),
+        "Can only evaluate 5, 6 or 7 cards, not {}",
+        cards.count()
+    );
+    let tables = tables();
+    let bits = cards.bits();
+    let suits = [
+        bits & RANK_MASK,
+        (bits >> NUM_RANKS) & RANK_MASK,
+        (bits >> (2 * NUM_RANKS)) & RANK_MASK,
+        (bits >> (3 * NUM_RANKS)) & RANK_MASK,
+    ];
+
+    // With at most 7 cards only one suit can hold five of them, and the two cards left over
+    // can't make quads or a full house with the flush cards, so a flush is the best hand unless
+    // it is also a straight flush. That holds whether or not a flush beats a full house.
+    for &suit in &suits {
+        if suit.count_ones() >= 5 {
+            let low = straight_low(tables, suit, ranking);
+            if low != 0 {
+                return HandStrength::ranked(ranking, 9, low);
+            }
+            return HandStrength::ranked(ranking, 6, top(tables, suit, 5));
+        }
+    }
+
+    let [s0, s1, s2, s3] = suits;
+    let any = s0 | s1 | s2 | s3;
+    let two_or_more = (s0 & s1) | (s0 & s2) | (s0 & s3) | (s1 & s2) | (s1 & s3) | (s2 & s3);
+    let three_or_more = (s0 & s1 & s2) | (s0 & s1 & s3) | (s0 & s2 & s3) | (s1 & s2 & s3);
+    let quads = s0 & s1 & s2 & s3;
+
+    if quads != 0 {
+        let quad = highest_bit(quads);
+        let kicker = top(tables, any & !quad, 1);
+        return HandStrength::ranked(ranking, 8, (rank_of_bit(quad) << 4) | kicker);
+    }
+
+    if three_or_more != 0 {
+        let trips = highest_bit(three_or_more);
+        let pairs = two_or_more & !trips;
+        if pairs != 0 {
+            let pair = top(tables, pairs, 1);
+            return HandStrength::ranked(ranking, 7, (rank_of_bit(trips) << 4) | pair);
+        }
+    }
+
+    let trips = |three_or_more| {
+        let trips = highest_bit(three_or_more);
+        let kickers = top(tables, any & !trips, 2);
+        HandStrength::ranked(ranking, 4, (rank_of_bit(trips) << 8) | kickers)
+    };
+    if ranking.trips_beat_straight() && three_or_more != 0 {
+        return trips(three_or_more);
+    }
+
+    let low = straight_low(tables, any, ranking);
+    if low != 0 {
+        return HandStrength::ranked(ranking, 5, low);
+    }
+
+    if three_or_more != 0 {
+        return trips(three_or_more);
+    }
+
+    if two_or_more != 0 {
+        let high_pair = highest_bit(two_or_more);
+        let low_pairs = two_or_more & !high_pair;
+        if low_pairs != 0 {
+            let low_pair = highest_bit(low_pairs);
+            let kicker = top(tables, any & !high_pair & !low_pair, 1);
+            let score = (rank_of_bit(high_pair) << 8) | (rank_of_bit(low_pair) << 4) | kicker;
+            return HandStrength::ranked(ranking, 3, score);
+        }
+        let kickers = top(tables, any & !high_pair, 3);
+        return HandStrength::ranked(ranking, 2, (rank_of_bit(high_pair) << 12) | kickers);
+    }
+
+    HandStrength::ranked(ranking, 1, top(tables, any, 5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+    use crate::hands::Hand;
+    use proptest::prelude::*;
+    use proptest::sample::subsequence;
+    use rand::thread_rng;
+    use std::cmp::Ordering;
+    use std::collections::{HashMap, HashSet};
+
+    fn make_set(cards_str: &str) -> CardSet {
+        parse_cards(cards_str).unwrap().into_iter().collect()
+    }
+
+    fn best_hand_strength(cards: CardSet) -> HandStrength {
+        let cards: Vec<Card> = cards.iter().collect();
+        cards
+            .iter()
+            .combinations(5)
+            .map(|hand| Hand::new(hand).strength())
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_evaluate_hand_types() {
+        let cases = [
+            ("Jh Th Ah Kh Qh 2c 2d", HandType::StraightFlush),
+            ("5d 2d Ad 3d 4d 6c", HandType::StraightFlush),
+            ("Jh Jd Js Jc 7d 7c 7h", HandType::Quads),
+            ("3h 2s 3c 2c 3d 2d Ah", HandType::FullHouse),
+            ("3h 3s 3c 2c 2d Ah Kh", HandType::FullHouse),
+            ("3h 2h 4h 5h 9h 7h 6c", HandType::Flush),
+            ("8d 7h 9d 6s Th 5c", HandType::Straight),
+            ("5d 2s Ah 3d 4c Kd Kc", HandType::Straight),
+            ("2s 2h 3h Qs 2c", HandType::ThreeOfAKind),
+            ("As 3c Ah Kd 3h Kc 2d", HandType::TwoPair),
+            ("As Kc 7s Kd 9d", HandType::Pair),
+            ("2s Qh 7c Kd 8d 4c 3d", HandType::HighCard),
+        ];
+        for (cards_str, hand_type) in cases {
+            let cards = make_set(cards_str);
+            assert_eq!(evaluate(cards).hand_type(), hand_type, "{}", cards_str);
+            assert_eq!(evaluate(cards), best_hand_strength(cards), "{}", cards_str);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_picks_best_five_of_seven() {
+        let cases = [
+            ("Ah Kh Qh Jh Th 9h 8h", "Ah Kh Qh Jh Th"),
+            ("9h 8h 7h 6h 5h 4h Ad", "9h 8h 7h 6h 5h"),
+            ("Th 9h 8h 7h 6h 6c 6d", "Th 9h 8h 7h 6h"),
+            ("Jh Jd Js Jc 2d 2c Ah", "Jh Jd Js Jc Ah"),
+            ("Jh Jd Js Jc Qd Qc Qh", "Jh Jd Js Jc Qd"),
+            ("Kc Kd Ks 7c 7d 7h 2s", "Kc Kd Ks 7c 7d"),
+            ("2c 2d 2s 7c 7d Ah Ad", "2c 2d 2s Ah Ad"),
+            ("Ah 2h 5h 9h Jh Kh 3h", "Ah Kh Jh 9h 5h"),
+            ("Ah 2h 5h 9h Jh Ac Ad", "Ah 2h 5h 9h Jh"),
+            ("2c 3d 4h 5s 6c 7d Ah", "3d 4h 5s 6c 7d"),
+            ("Ac 2d 3h 4s 5c Kd Kh", "Ac 2d 3h 4s 5c"),
+            ("Tc Td Ts 9h 8s 7d 6c", "Tc 9h 8s 7d 6c"),
+            ("9c 9d 9s Ah 2s 3d Kc", "9c 9d 9s Ah Kc"),
+            ("As Ad Kc Kd Qh Qs 2c", "As Ad Kc Kd Qh"),
+            ("Ac Ad 3h 3s 5c 6d 2h", "Ac Ad 3h 3s 6d"),
+            ("8c 8d As Kd 9c 7h 2c", "8c 8d As Kd 9c"),
+            ("As Kd 9c 7h 5s 3d 2c", "As Kd 9c 7h 5s"),
+        ];
+        for (seven, five) in cases {
+            assert_eq!(
+                evaluate(make_set(seven)),
+                evaluate(make_set(five)),
+                "{}",
+                seven
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Can only evaluate 5, 6 or 7 cards, not 4")]
+    fn test_evaluate_rejects_too_few_cards() {
+        evaluate(make_set("Ah Kh Qh Jh"));
+    }
+
+    #[test]
+    fn test_evaluate_matches_hand_for_random_cards() {
+        let mut rng = thread_rng();
+        for num_cards in 5..=7 {
+            for _ in 0..20000 {
+                let cards = CardSet::full_deck().sample(num_cards, &mut rng);
+                assert_eq!(evaluate(cards), best_hand_strength(cards), "{:?}", cards);
+            }
+        }
+    }
+
+    #[test]
+    fn test_class_rank_and_percentile() {
+        let royal_flush = evaluate(make_set("Ah Kh Qh Jh Th"));
+        assert_eq!(royal_flush.class_rank(), 1);
+        assert_eq!(royal_flush.percentile(), 100. * (1. - 4. / 2_598_960.));
+        let worst = evaluate(make_set("7h 5d 4c 3s 2s"));
+        assert_eq!(worst.class_rank(), NUM_HAND_CLASSES);
+        assert_eq!(worst.percentile(), 0.);
+        assert_eq!(evaluate(make_set("5h 4h 3h 2h Ah")).class_rank(), 10);
+        assert_eq!(evaluate(make_set("Ah Ad Ac As Kd")).class_rank(), 11);
+        assert_eq!(evaluate(make_set("Ah Ad Ac As Kd 2c 3c")).class_rank(), 11);
+        // The next worst hand beats the 1020 ways of making 7-5-4-3-2 offsuit.
+        assert_eq!(
+            evaluate(make_set("7h 6d 4c 3s 2s")).percentile(),
+            100. * 1020. / 2_598_960.
+        );
+    }
+
+    #[test]
+    fn test_short_deck_class_rank_and_percentile() {
+        let short_deck = |cards| evaluate_with_ranking(make_set(cards), HandRanking::ShortDeck);
+        let royal_flush = short_deck("Ah Kh Qh Jh Th");
+        assert_eq!(royal_flush.ranking(), HandRanking::ShortDeck);
+        assert_eq!(royal_flush.class_rank(), 1);
+        assert_eq!(royal_flush.percentile(), 100. * (1. - 4. / 376_992.));
+        // J-9-8-7-6 offsuit is the worst hand in the short deck, though it beats the lower high
+        // cards in a full deck
+        let worst = short_deck("Jh 9d 8c 7s 6s");
+        assert_eq!(worst.percentile(), 0.);
+        assert!(evaluate(make_set("Jh 9d 8c 7s 6s")).percentile() > 0.);
+        // Flushes rank above full houses
+        let flush = short_deck("Ah Th 9h 7h 6h");
+        let full_house = short_deck("Ah Ad Ac Ks Kd");
+        assert!(flush.class_rank() < full_house.class_rank());
+        let standard = |cards| evaluate(make_set(cards));
+        assert!(standard("Ah Th 9h 7h 6h").class_rank() > standard("Ah Ad Ac Ks Kd").class_rank());
+        // And with trips beating straights, trips rank above straights
+        let trips_beat_straight =
+            |cards| evaluate_with_ranking(make_set(cards), HandRanking::ShortDeckTripsBeatStraight);
+        assert!(
+            trips_beat_straight("6h 6d 6c 7s 8d").class_rank()
+                < trips_beat_straight("Ah Kd Qc Js Td").class_rank()
+        );
+    }
+
+    #[test]
+    fn test_all_five_card_hands() {
+        let mut counts: HashMap<HandStrength, u32> = HashMap::new();
+        for cards in CardSet::full_deck().combinations(5) {
+            *counts.entry(evaluate(cards)).or_default() += 1;
+        }
+        assert_eq!(counts.len(), NUM_HAND_CLASSES as usize);
+        assert_eq!(counts.values().sum::<u32>(), NUM_FIVE_CARD_HANDS);
+
+        let expected = [
+            (HandType::StraightFlush, 10, 40),
+            (HandType::Quads, 156, 624),
+            (HandType::FullHouse, 156, 3744),
+            (HandType::Flush, 1277, 5108),
+            (HandType::Straight, 10, 10200),
+            (HandType::ThreeOfAKind, 858, 54912),
+            (HandType::TwoPair, 858, 123552),
+            (HandType::Pair, 2860, 1098240),
+            (HandType::HighCard, 1277, 1302540),
+        ];
+        for (hand_type, num_classes, num_hands) in expected {
+            let classes: Vec<(&HandStrength, &u32)> = counts
+                .iter()
+                .filter(|(strength, _)| strength.hand_type() == hand_type)
+                .collect();
+            assert_eq!(classes.len(), num_classes, "{}", hand_type);
+            assert_eq!(
+                classes.iter().map(|(_, &n)| n).sum::<u32>(),
+                num_hands,
+                "{}",
+                hand_type
+            );
+        }
+
+        // Ranks run from best to worst and each percentile counts exactly the hands beaten.
+        let mut strengths: Vec<HandStrength> = counts.keys().copied().collect();
+        strengths.sort();
+        let mut beaten = 0;
+        for (i, strength) in strengths.iter().enumerate() {
+            assert_eq!(strength.class_rank() as usize, strengths.len() - i);
+            let percentile = 100. * beaten as f64 / NUM_FIVE_CARD_HANDS as f64;
+            assert_eq!(strength.percentile(), percentile);
+            assert_eq!(counts[strength], hands_in_class(strength.hand_type()));
+            beaten += counts[strength];
+        }
+    }
+
+    #[test]
+    fn test_all_seven_card_hands() {
+        let royal_flush = evaluate(make_set("Ah Kh Qh Jh Th"));
+        let mut royal_flushes = 0;
+        let mut hand_counts: HashMap<HandType, u32> = HashMap::new();
+        let mut classes: HashSet<HandStrength> = HashSet::new();
+        for cards in CardSet::full_deck().combinations(7) {
+            let strength = evaluate(cards);
+            if strength == royal_flush {
+                royal_flushes += 1;
+            }
+            *hand_counts.entry(strength.hand_type()).or_default() += 1;
+            classes.insert(strength);
+        }
+        assert_eq!(hand_counts.values().sum::<u32>(), 133_784_560);
+        assert_eq!(classes.len(), 4824);
+        assert_eq!(royal_flushes, 4324);
+
+        let expected = [
+            (HandType::StraightFlush, 10, 41_584),
+            (HandType::Quads, 156, 224_848),
+            (HandType::FullHouse, 156, 3_473_184),
+            (HandType::Flush, 1277, 4_047_644),
+            (HandType::Straight, 10, 6_180_020),
+            (HandType::ThreeOfAKind, 575, 6_461_620),
+            (HandType::TwoPair, 763, 31_433_400),
+            (HandType::Pair, 1470, 58_627_800),
+            (HandType::HighCard, 407, 23_294_460),
+        ];
+        for (hand_type, num_classes, num_hands) in expected {
+            let type_classes = classes
+                .iter()
+                .filter(|strength| strength.hand_type() == hand_type)
+                .count();
+            assert_eq!(type_classes, num_classes, "{}", hand_type);
+            assert_eq!(hand_counts[&hand_type], num_hands, "{}", hand_type);
+        }
+    }
+
+    fn cards_strategy(sizes: std::ops::RangeInclusive<usize>) -> impl Strategy<Value = Vec<Card>> {
+        subsequence(Card::create_deck(), sizes).prop_shuffle()
+    }
+
+    fn five_card_strength(cards: &[Card]) -> HandStrength {
+        Hand::new(cards.iter().collect()).strength()
+    }
+
+    proptest! {
+        #[test]
+        fn prop_order_of_cards_does_not_matter(cards in cards_strategy(5..=5)) {
+            let set: CardSet = cards.iter().copied().collect();
+            prop_assert_eq!(five_card_strength(&cards), evaluate(set));
+            let mut reversed = cards.clone();
+            reversed.reverse();
+            prop_assert_eq!(five_card_strength(&reversed), five_card_strength(&cards));
+        }
+
+        #[test]
+        fn prop_relabelling_suits_does_not_matter(
+            cards in cards_strategy(5..=7),
+            suits in Just(Suit::ALL.to_vec()).prop_shuffle(),
+        ) {
+            let set: CardSet = cards.iter().copied().collect();
+            let relabelled: CardSet = cards
+                .iter()
+                .map(|card| Card::new(card.rank(), suits[card.suit() as usize]))
+                .collect();
+            prop_assert_eq!(evaluate(relabelled), evaluate(set));
+        }
+
+        #[test]
+        fn prop_hands_are_totally_ordered(
+            first in cards_strategy(5..=5),
+            second in cards_strategy(5..=5),
+            third in cards_strategy(5..=5),
+        ) {
+            let hands: Vec<Hand> = [&first, &second, &third]
+                .into_iter()
+                .map(|cards| Hand::new(cards.iter().collect()))
+                .collect();
+            for a in &hands {
+                prop_assert_eq!(a.cmp(a), Ordering::Equal);
+                for b in &hands {
+                    prop_assert_eq!(a.cmp(b), b.cmp(a).reverse());
+                    prop_assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+                    prop_assert_eq!(a == b, a.cmp(b) == Ordering::Equal);
+                    prop_assert_eq!(a.cmp(b), a.strength().cmp(&b.strength()));
+                    prop_assert_eq!(
+                        a.cmp(b),
+                        b.strength().class_rank().cmp(&a.strength().class_rank())
+                    );
+                    for c in &hands {
+                        if a <= b && b <= c {
+                            prop_assert!(a <= c);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn best_ranked_strength(cards: CardSet, ranking: HandRanking) -> HandStrength {
+        let cards: Vec<Card> = cards.iter().collect();
+        cards
+            .iter()
+            .combinations(5)
+            .map(|hand| Hand::with_ranking(hand, ranking).strength())
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_evaluate_short_deck_matches_hand_for_random_cards() {
+        let mut rng = thread_rng();
+        for ranking in [
+            HandRanking::ShortDeck,
+            HandRanking::ShortDeckTripsBeatStraight,
+        ] {
+            for num_cards in 5..=7 {
+                for _ in 0..10000 {
+                    let cards = CardSet::short_deck().sample(num_cards, &mut rng);
+                    assert_eq!(
+                        evaluate_with_ranking(cards, ranking),
+                        best_ranked_strength(cards, ranking),
+                        "{:?}",
+                        cards
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_short_deck_hands() {
+        let ranking = HandRanking::ShortDeckTripsBeatStraight;
+        // Three of a kind is kept over the straight it's part of
+        let cards = make_set("9c 9d 9s Th Jh Qd Kc");
+        assert_eq!(evaluate(cards).hand_type(), HandType::Straight);
+        assert_eq!(
+            evaluate_with_ranking(cards, ranking).hand_type(),
+            HandType::ThreeOfAKind
+        );
+        let cards = make_set("Ac 6d 7s 8h 9h Kd");
+        assert_eq!(
+            evaluate_with_ranking(cards, HandRanking::ShortDeck),
+            evaluate_with_ranking(make_set("Ac 6d 7s 8h 9h"), HandRanking::ShortDeck)
+        );
+        assert_eq!(
+            evaluate_with_ranking(cards, HandRanking::ShortDeck).hand_type(),
+            HandType::Straight
+        );
+        assert_eq!(
+            evaluate_with_ranking(make_set("Ac 6c 7c 8c 9c"), HandRanking::ShortDeck).hand_type(),
+            HandType::StraightFlush
+        );
+    }
+
+    #[test]
+    fn test_all_short_deck_five_card_hands() {
+        let mut counts: HashMap<HandType, u32> = HashMap::new();
+        let mut strongest: HashMap<HandType, HandStrength> = HashMap::new();
+        let mut weakest: HashMap<HandType, HandStrength> = HashMap::new();
+        let mut classes: HashMap<HandStrength, u32> = HashMap::new();
+        for cards in CardSet::short_deck().combinations(5) {
+            let strength = evaluate_with_ranking(cards, HandRanking::ShortDeck);
+            *classes.entry(strength).or_default() += 1;
+            let hand_type = strength.hand_type();
+            *counts.entry(hand_type).or_default() += 1;
+            let best = strongest.entry(hand_type).or_insert(strength);
+            *best = strength.max(*best);
+            let worst = weakest.entry(hand_type).or_insert(strength);
+            *worst = strength.min(*worst);
+        }
+        assert_eq!(counts.values().sum::<u32>(), 376_992);
+        let expected = [
+            (HandType::StraightFlush, 24),
+            (HandType::Flush, 480),
+            (HandType::Quads, 288),
+            (HandType::FullHouse, 1728),
+            (HandType::Straight, 6120),
+            (HandType::ThreeOfAKind, 16128),
+            (HandType::TwoPair, 36288),
+            (HandType::Pair, 193536),
+            (HandType::HighCard, 122400),
+        ];
+        for (hand_type, num_hands) in expected {
+            assert_eq!(counts[&hand_type], num_hands, "{}", hand_type);
+        }
+        // Every flush beats every full house, and the types don't overlap in strength
+        assert!(weakest[&HandType::Flush] > strongest[&HandType::FullHouse]);
+        for pair in expected.windows(2) {
+            let (stronger, weaker) = (pair[0].0, pair[1].0);
+            if stronger != HandType::Flush {
+                assert!(weakest[&stronger] > strongest[&weaker], "{}", stronger);
+            }
+        }
+
+        // Ranks and percentiles are among the short-deck hands
+        let mut strengths: Vec<HandStrength> = classes.keys().copied().collect();
+        strengths.sort();
+        let mut beaten = 0;
+        for (i, strength) in strengths.iter().enumerate() {
+            assert_eq!(strength.class_rank() as usize, strengths.len() - i);
+            assert_eq!(strength.percentile(), 100. * beaten as f64 / 376_992.);
+            beaten += classes[strength];
+        }
+    }
+}
@@ -1,72 +1,803 @@
-use crate::cards::Card;
-use crate::hands::Hand;
+use crate::card_set::CardSet;
+use crate::cards::{parse_cards, Card, NUM_CARDS};
+use crate::evaluator::{evaluate_with_ranking, HandStrength};
+use crate::hands::{Hand, HandRanking};
+use crate::lowball::LowStrength;
+use crate::range::{Combo, Range, RangeParseError};
+use crate::request::MAX_REQUEST_THREADS;
+use crate::variant::{Holdem, Omaha, PotSplit, Stud, Variant, HAND_SIZE};
 use itertools::Itertools;
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::Rng;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashSet;
-use std::iter::FromIterator;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The game being played, which decides how many hole cards each player is dealt, how they can
+/// be combined with the board (if there is one) to make a hand, and who wins the pot.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameType {
+    /// Texas hold'em: two hole cards, and the best five of the seven cards play.
+    #[default]
+    Holdem,
+    /// Omaha (PLO4): four hole cards, of which exactly two must be used with exactly three from
+    /// the board.
+    #[serde(alias = "plo", alias = "plo4")]
+    Omaha,
+    /// Five-card Omaha (PLO5), played like Omaha with five hole cards.
+    #[serde(alias = "plo5")]
+    Omaha5,
+    /// Six-card Omaha (PLO6), played like Omaha with six hole cards.
+    #[serde(alias = "plo6")]
+    Omaha6,
+    /// Omaha hi-lo (PLO8): Omaha where the pot is split between the best high hand and the best
+    /// ace-to-five low of eight or better, again made from two hole cards and three board cards.
+    /// If nobody has a low, the high hand takes the whole pot.
+    #[serde(alias = "plo8", alias = "omaha8")]
+    OmahaHiLo,
+    /// Short-deck (6+) hold'em: hold'em with the twos to fives taken out of the deck, where a
+    /// flush beats a full house and A-6-7-8-9 is the lowest straight.
+    #[serde(alias = "6plus")]
+    ShortDeck,
+    /// Short-deck hold'em where three of a kind also beats a straight.
+    ShortDeckTripsBeatStraight,
+    /// Seven-card stud: no board, and the best five of each player's own seven cards play. The
+    /// cards a player is known to hold are given as their seat, either all together or as their
+    /// down and up cards (`Seat::Stud`), and the upcards of players who have folded go in the
+    /// dead cards.
+    #[serde(alias = "stud_high")]
+    Stud,
+    /// Seven-card stud hi-lo (stud 8): stud with the pot split like Omaha hi-lo, between the best
+    /// high hand and the best eight-or-better low.
+    #[serde(alias = "stud8")]
+    StudHiLo,
+    /// Razz: stud where the best ace-to-five low hand takes the whole pot, whether or not it's
+    /// paired.
+    Razz,
+}
+
+impl GameType {
+    /// The rules of the game: how it's dealt, how hands are made and who wins the pot.
+    pub fn variant(&self) -> &'static dyn Variant {
+        match self {
+            GameType::Holdem => &Holdem {
+                ranking: HandRanking::Standard,
+            },
+            GameType::Omaha => &Omaha {
+                num_hole_cards: 4,
+                pot_split: PotSplit::High,
+            },
+            GameType::Omaha5 => &Omaha {
+                num_hole_cards: 5,
+                pot_split: PotSplit::High,
+            },
+            GameType::Omaha6 => &Omaha {
+                num_hole_cards: 6,
+                pot_split: PotSplit::High,
+            },
+            GameType::OmahaHiLo => &Omaha {
+                num_hole_cards: 4,
+                pot_split: PotSplit::HiLo { qualifier: 8 },
+            },
+            GameType::ShortDeck => &Holdem {
+                ranking: HandRanking::ShortDeck,
+            },
+            GameType::ShortDeckTripsBeatStraight => &Holdem {
+                ranking: HandRanking::ShortDeckTripsBeatStraight,
+            },
+            GameType::Stud => &Stud {
+                pot_split: PotSplit::High,
+            },
+            GameType::StudHiLo => &Stud {
+                pot_split: PotSplit::HiLo { qualifier: 8 },
+            },
+            GameType::Razz => &Stud {
+                pot_split: PotSplit::Low,
+            },
+        }
+    }
+}
+
+impl GameType {
+    /// Whether this is a stud game, where each player is dealt their own down and up cards.
+    pub fn is_stud(&self) -> bool {
+        matches!(self, GameType::Stud | GameType::StudHiLo | GameType::Razz)
+    }
+}
+
+impl fmt::Display for GameType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameType::Holdem => write!(f, "Hold'em"),
+            GameType::Omaha => write!(f, "Omaha"),
+            GameType::Omaha5 => write!(f, "5-card Omaha"),
+            GameType::Omaha6 => write!(f, "6-card Omaha"),
+            GameType::OmahaHiLo => write!(f, "Omaha hi-lo"),
+            GameType::ShortDeck => write!(f, "Short-deck hold'em"),
+            GameType::ShortDeckTripsBeatStraight => {
+                write!(f, "Short-deck hold'em (trips beat straights)")
+            }
+            GameType::Stud => write!(f, "Seven-card stud"),
+            GameType::StudHiLo => write!(f, "Seven-card stud hi-lo"),
+            GameType::Razz => write!(f, "Razz"),
+        }
+    }
+}
 
 // A GameSpec represents incomplete information about a game situation
 // which can be used to construct a Game by randomly filling in the
 // unknown cards.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GameSpec {
+    #[serde(default)]
     pub board: Vec<Card>,
-    pub hole_cards: Vec<(Card, Card)>,
+    pub seats: Vec<Seat>,
+    // Cards known to be out of play, e.g. a folded hand that was shown or a card flashed by the
+    // dealer. They are taken out of the deck before anything is dealt.
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+    #[serde(default)]
+    pub game: GameType,
+}
+
+// The most down and up cards a stud player is dealt: two down, four up and the last one down.
+const MAX_STUD_DOWN_CARDS: usize = 3;
+const MAX_STUD_UP_CARDS: usize = 4;
+
+/// What we know about one player's hole cards: some or all of the cards themselves, a range of
+/// hands they could be holding, or nothing at all. Unknown cards are dealt at random from the
+/// deck. Ranges are of two-card hands, so they are only allowed in hold'em.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Seat {
+    Known(Vec<Card>),
+    /// A stud player's known cards, kept apart as the cards dealt face down (at most three, and
+    /// usually only known for the hero) and face up (at most four).
+    Stud {
+        down: Vec<Card>,
+        up: Vec<Card>,
+    },
+    Range(Range),
+    Random,
+}
+
+impl Seat {
+    // The cards known to be in this seat's hand, down and up alike.
+    fn known_cards(&self) -> impl Iterator<Item = Card> + '_ {
+        let (first, second): (&[Card], &[Card]) = match self {
+            Seat::Known(cards) => (cards, &[]),
+            Seat::Stud { down, up } => (down, up),
+            Seat::Range(_) | Seat::Random => (&[], &[]),
+        };
+        first.iter().chain(second).copied()
+    }
+}
+
+/// Parses a seat from cards, e.g. "AsKd" or just "As", a stud player's down and up cards
+/// separated by a slash, e.g. "As2d/7c" or "/Kh", range notation, e.g. "QQ+, AKs", or "random".
+impl FromStr for Seat {
+    type Err = RangeParseError;
+
+    fn from_str(s: &str) -> Result<Seat, RangeParseError> {
+        if s.trim().eq_ignore_ascii_case("random") {
+            return Ok(Seat::Random);
+        }
+        if let Some((down, up)) = s.split_once('/') {
+            let invalid_hand = |_| RangeParseError::InvalidHand(s.trim().to_string());
+            return Ok(Seat::Stud {
+                down: parse_cards(down).map_err(invalid_hand)?,
+                up: parse_cards(up).map_err(invalid_hand)?,
+            });
+        }
+        match parse_cards(s) {
+            Ok(cards) if !cards.is_empty() => Ok(Seat::Known(cards)),
+            _ => Ok(Seat::Range(s.parse()?)),
+        }
+    }
+}
+
+impl fmt::Display for Seat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Seat::Known(cards) => write!(f, "{}", cards.iter().format("")),
+            Seat::Stud { down, up } => {
+                write!(f, "{}/{}", down.iter().format(""), up.iter().format(""))
+            }
+            Seat::Range(range) => write!(f, "{}", range),
+            Seat::Random => write!(f, "random"),
+        }
+    }
 }
 
+// Seats are serialized as strings in the same form that they are parsed from.
+impl Serialize for Seat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Seat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Seat, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// The ways in which a GameSpec can describe an impossible game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameSpecError {
+    DuplicateCard(Card),
+    CardNotInDeck(Card),
+    BoardTooLong(usize),
+    TooFewPlayers(usize),
+    TooManyPlayers(usize),
+    TooManyDeadCards(usize),
+    TooManyHoleCards(usize),
+    TooManyDownCards(usize),
+    TooManyUpCards(usize),
+    UpCardsNotSupported(usize),
+    RangeNotSupported(usize),
+    EmptyRange(usize),
+    NoPossibleDeal,
+    CannotEnumerateUnknownHands,
+    NoSimulations,
+    InvalidPrecision,
+    TooManyThreads(usize),
+}
+
+impl fmt::Display for GameSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameSpecError::DuplicateCard(card) => write!(f, "card {} appears more than once", card),
+            GameSpecError::CardNotInDeck(card) => {
+                write!(f, "card {} isn't in the deck for this game", card)
+            }
+            GameSpecError::BoardTooLong(len) => {
+                write!(f, "board has {} cards, more than the game deals", len)
+            }
+            GameSpecError::TooFewPlayers(n) => write!(f, "{} players given, at least 2 needed", n),
+            GameSpecError::TooManyPlayers(n) => {
+                write!(f, "{} players is too many to deal from one deck", n)
+            }
+            GameSpecError::TooManyDeadCards(n) => {
+                write!(f, "{} dead cards leave too few cards to deal everyone", n)
+            }
+            GameSpecError::TooManyHoleCards(player) => {
+                write!(
+                    f,
+                    "player {} has more hole cards than the game deals",
+                    player
+                )
+            }
+            GameSpecError::TooManyDownCards(player) => write!(
+                f,
+                "player {} has more than {} down cards",
+                player, MAX_STUD_DOWN_CARDS
+            ),
+            GameSpecError::TooManyUpCards(player) => write!(
+                f,
+                "player {} has more than {} up cards",
+                player, MAX_STUD_UP_CARDS
+            ),
+            GameSpecError::UpCardsNotSupported(player) => write!(
+                f,
+                "player {} has down and up cards, but only stud games deal them",
+                player
+            ),
+            GameSpecError::RangeNotSupported(player) => {
+                write!(
+                    f,
+                    "player {} has a range, but ranges are only supported in hold'em games, \
+                     including short deck",
+                    player
+                )
+            }
+            GameSpecError::EmptyRange(player) => {
+                write!(f, "player {} has no possible hands in their range", player)
+            }
+            GameSpecError::NoPossibleDeal => {
+                write!(f, "the players' ranges can't all be dealt at once")
+            }
+            GameSpecError::CannotEnumerateUnknownHands => {
+                write!(
+                    f,
+                    "exact enumeration only supports known hands, not ranges or random ones"
+                )
+            }
+            GameSpecError::NoSimulations => write!(f, "at least one simulation is needed"),
+            GameSpecError::InvalidPrecision => write!(
+                f,
+                "a precision that can't be reached needs another limit to stop at"
+            ),
+            GameSpecError::TooManyThreads(n) => {
+                write!(
+                    f,
+                    "{} threads asked for, at most {} allowed",
+                    n, MAX_REQUEST_THREADS
+                )
+            }
+        }
+    }
+}
+
+impl Error for GameSpecError {}
+
+impl GameSpec {
+    /// Checks that the spec describes a game that can actually be dealt: at least two players,
+    /// no more board cards than the game deals, enough cards in the deck for everyone once the
+    /// dead cards are taken out, no player given more hole cards than the game deals (or, in
+    /// stud, more than three down cards or four up cards), no card
+    /// used twice (including dead cards) or missing from the game's deck, and some way of
+    /// dealing every player a hand from their range.
+    pub fn validate(&self) -> Result<(), GameSpecError> {
+        let num_players = self.seats.len();
+        let variant = self.game.variant();
+        let num_hole_cards = variant.num_hole_cards();
+        let deck = variant.deck();
+        if num_players < 2 {
+            return Err(GameSpecError::TooFewPlayers(num_players));
+        }
+        let board_size = variant.board_size();
+        if num_hole_cards * num_players + board_size > deck.count() {
+            return Err(GameSpecError::TooManyPlayers(num_players));
+        }
+        if self.board.len() > board_size {
+            return Err(GameSpecError::BoardTooLong(self.board.len()));
+        }
+        if num_hole_cards * num_players + board_size + self.dead_cards.len() > deck.count() {
+            return Err(GameSpecError::TooManyDeadCards(self.dead_cards.len()));
+        }
+        for (player, seat) in self.seats.iter().enumerate() {
+            match seat {
+                Seat::Known(cards) if cards.len() > num_hole_cards => {
+                    return Err(GameSpecError::TooManyHoleCards(player));
+                }
+                Seat::Stud { .. } if !self.game.is_stud() => {
+                    return Err(GameSpecError::UpCardsNotSupported(player));
+                }
+                Seat::Stud { down, .. } if down.len() > MAX_STUD_DOWN_CARDS => {
+                    return Err(GameSpecError::TooManyDownCards(player));
+                }
+                Seat::Stud { up, .. } if up.len() > MAX_STUD_UP_CARDS => {
+                    return Err(GameSpecError::TooManyUpCards(player));
+                }
+                Seat::Range(_) if num_hole_cards != 2 => {
+                    return Err(GameSpecError::RangeNotSupported(player));
+                }
+                _ => {}
+            }
+        }
+
+        let mut seen = CardSet::empty();
+        let hole_cards = self.seats.iter().flat_map(Seat::known_cards);
+        let known_cards = self.board.iter().chain(&self.dead_cards).copied();
+        for card in known_cards.chain(hole_cards) {
+            if !deck.contains(card) {
+                return Err(GameSpecError::CardNotInDeck(card));
+            }
+            if !seen.insert(card) {
+                return Err(GameSpecError::DuplicateCard(card));
+            }
+        }
+
+        // Cards outside the deck block range hands just like cards already dealt
+        let blocked = seen.union(CardSet::full_deck().difference(deck));
+        let mut ranges = Vec::new();
+        for (player, seat) in self.seats.iter().enumerate() {
+            if let Seat::Range(range) = seat {
+                let range = range.remove_blocked(blocked);
+                if range.is_empty() {
+                    return Err(GameSpecError::EmptyRange(player));
+                }
+                ranges.push(range);
+            }
+        }
+        if !can_deal_ranges(&ranges) {
+            return Err(GameSpecError::NoPossibleDeal);
+        }
+        Ok(())
+    }
+
+    /// A hold'em spec for one player against `num_opponents` others holding random hands.
+    pub fn against_random(board: Vec<Card>, hero: Seat, num_opponents: usize) -> GameSpec {
+        let mut seats = vec![hero];
+        seats.resize(num_opponents + 1, Seat::Random);
+        GameSpec {
+            board,
+            seats,
+            dead_cards: Vec::new(),
+            game: GameType::Holdem,
+        }
+    }
+
+    /// Whether any player's hole cards aren't fully known, and so have to be dealt each trial.
+    pub fn has_unknown_hands(&self) -> bool {
+        let num_hole_cards = self.game.variant().num_hole_cards();
+        self.seats.iter().any(|seat| match seat {
+            Seat::Known(_) | Seat::Stud { .. } => seat.known_cards().count() < num_hole_cards,
+            Seat::Range(_) | Seat::Random => true,
+        })
+    }
+}
+
+// Whether every range can be dealt a combo without any two sharing a card. The ranges are tried
+// in turn, smallest first, remembering which partial deals turned out to be dead ends. Of the
+// cards dealt so far only those that the remaining ranges could use make a difference, so deals
+// that differ in other cards are the same dead end. That keeps the search to the number of ways
+// the contested cards can be shared out, rather than every order of dealing them.
+fn can_deal_ranges(ranges: &[Range]) -> bool {
+    let mut ranges: Vec<Vec<CardSet>> = ranges
+        .iter()
+        .map(|range| {
+            range
+                .combos()
+                .iter()
+                .map(|&((card_1, card_2), _)| [card_1, card_2].into_iter().collect())
+                .collect()
+        })
+        .collect();
+    ranges.sort_by_key(|combos| combos.len());
+    // The cards that each range and those after it could use
+    let mut remaining_cards = vec![CardSet::empty(); ranges.len()];
+    let mut cards = CardSet::empty();
+    for (combos, remaining_cards) in ranges.iter().zip(&mut remaining_cards).rev() {
+        cards = combos
+            .iter()
+            .fold(cards, |cards, &combo| cards.union(combo));
+        *remaining_cards = cards;
+    }
+    can_deal_combos(
+        &ranges,
+        &remaining_cards,
+        CardSet::empty(),
+        &mut HashSet::new(),
+    )
+}
+
+fn can_deal_combos(
+    ranges: &[Vec<CardSet>],
+    remaining_cards: &[CardSet],
+    dealt: CardSet,
+    dead_ends: &mut HashSet<(usize, CardSet)>,
+) -> bool {
+    let Some((combos, rest)) = ranges.split_first() else {
+        return true;
+    };
+    let deal = (rest.len(), dealt.intersection(remaining_cards[0]));
+    if dead_ends.contains(&deal) {
+        return false;
+    }
+    let can_deal = combos.iter().any(|&combo| {
+        combo.is_disjoint(dealt)
+            && can_deal_combos(rest, &remaining_cards[1..], dealt.union(combo), dead_ends)
+    });
+    if !can_deal {
+        dead_ends.insert(deal);
+    }
+    can_deal
+}
+
+// How many times to draw from a whole range for a combo that doesn't clash with the cards
+// already dealt, before picking from the combos that don't clash directly.
+const MAX_CLASHING_DRAWS: usize = 16;
+
+// The combos of a range that a seat can actually be dealt, with running totals of their weights
+// so that one can be picked at random in proportion to its weight. It also keeps the number and
+// total weight of the combos using each card, and the weight of each pair of cards, so that the
+// part of the range blocked by the cards other players were dealt can be added up without going
+// through the whole range.
+#[derive(Clone)]
+struct SeatRange {
+    combos: Vec<Combo>,
+    weights: Vec<f64>,
+    cumulative_weights: Vec<f64>,
+    cards: CardSet,
+    card_counts: Vec<usize>,
+    card_weights: Vec<f64>,
+    pair_weights: Vec<f64>,
+}
+
+impl SeatRange {
+    fn new(range: &Range, known_cards: CardSet) -> SeatRange {
+        let range = range.remove_blocked(known_cards);
+        let combos: Vec<Combo> = range.combos().iter().map(|&(combo, _)| combo).collect();
+        let weights: Vec<f64> = range.combos().iter().map(|&(_, weight)| weight).collect();
+        let cumulative_weights = weights
+            .iter()
+            .scan(0., |total, &weight| {
+                *total += weight;
+                Some(*total)
+            })
+            .collect();
+        let mut cards = CardSet::empty();
+        let mut card_counts = vec![0; NUM_CARDS as usize];
+        let mut card_weights = vec![0.; NUM_CARDS as usize];
+        let mut pair_weights = vec![0.; NUM_CARDS as usize * NUM_CARDS as usize];
+        for (&(card_1, card_2), &weight) in combos.iter().zip(&weights) {
+            for card in [card_1, card_2] {
+                cards.insert(card);
+                card_counts[card.index() as usize] += 1;
+                card_weights[card.index() as usize] += weight;
+            }
+            pair_weights[pair_index(card_1, card_2)] = weight;
+            pair_weights[pair_index(card_2, card_1)] = weight;
+        }
+        SeatRange {
+            combos,
+            weights,
+            cumulative_weights,
+            cards,
+            card_counts,
+            card_weights,
+            pair_weights,
+        }
+    }
+
+    fn total_weight(&self) -> f64 {
+        *self.cumulative_weights.last().unwrap()
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let target = rng.gen_range(0. ..self.total_weight());
+        self.cumulative_weights
+            .partition_point(|&weight| weight <= target)
+            .min(self.combos.len() - 1)
+    }
+
+    // Picks a combo that doesn't use any of the dealt cards, at random in proportion to the
+    // weights, and returns it along with the fraction of the range's weight it was picked from.
+    // None if every combo uses a dealt card.
+    fn sample_avoiding<R: Rng + ?Sized>(
+        &self,
+        dealt: CardSet,
+        rng: &mut R,
+    ) -> Option<(usize, f64)> {
+        let contested = dealt.intersection(self.cards);
+        if contested.is_empty() {
+            return Some((self.sample(rng), 1.));
+        }
+        let is_blocked =
+            |(card_1, card_2): Combo| contested.contains(card_1) || contested.contains(card_2);
+
+        // Combos of two dealt cards are counted for each of them, so once too often
+        let mut num_blocked = 0;
+        let mut blocked_weight = 0.;
+        for card in contested {
+            num_blocked += self.card_counts[card.index() as usize];
+            blocked_weight += self.card_weights[card.index() as usize];
+            for other_card in contested {
+                let pair_weight = self.pair_weights[pair_index(card, other_card)];
+                if other_card.index() > card.index() && pair_weight > 0. {
+                    num_blocked -= 1;
+                    blocked_weight -= pair_weight;
+                }
+            }
+        }
+        if num_blocked == self.combos.len() {
+            return None;
+        }
+        let total = self.total_weight();
+        for _ in 0..MAX_CLASHING_DRAWS {
+            let index = self.sample(rng);
+            if !is_blocked(self.combos[index]) {
+                return Some((index, (total - blocked_weight) / total));
+            }
+        }
+
+        // Most of the range is blocked, so pick from what's left of it
+        let unblocked = || (0..self.combos.len()).filter(|&index| !is_blocked(self.combos[index]));
+        let available: f64 = unblocked().map(|index| self.weights[index]).sum();
+        let mut target = rng.gen_range(0. ..available);
+        let mut picked = None;
+        for index in unblocked() {
+            picked = Some(index);
+            target -= self.weights[index];
+            if target < 0. {
+                break;
+            }
+        }
+        picked.map(|index| (index, available / total))
+    }
+}
+
+fn pair_index(card_1: Card, card_2: Card) -> usize {
+    card_1.index() as usize * NUM_CARDS as usize + card_2.index() as usize
+}
+
+// A Game is a GameSpec with the unknown cards filled in. It is built once from a spec and then
+// re-dealt for each simulated trial: `reset` returns it to the state described by the spec,
+// `deal_hole_cards` picks a hand for each player given a range and fills in the unknown cards of
+// the other players, and `deal_down_to_river` draws the rest of the board from the remaining
+// deck. None of these allocate.
+#[derive(Clone)]
 pub struct Game {
-    deck: Vec<Card>,
+    variant: &'static dyn Variant,
+    undealt: CardSet,
+    deck: CardSet,
+    num_spec_board_cards: usize,
     board: Vec<Card>,
-    hole_cards: Vec<(Card, Card)>,
+    hole_cards: Vec<Vec<Card>>,
+    seat_ranges: Vec<Option<SeatRange>>,
+    dealt_combos: Vec<Option<usize>>,
+    // The players with cards to be dealt from the deck, along with how many of their cards are
+    // already known. The known cards come first in their hole cards.
+    unknown_seats: Vec<(usize, usize)>,
 }
 
 impl Game {
-    pub fn from_spec(spec: &GameSpec) -> Game {
-        let mut cards_set: HashSet<Card> = HashSet::from_iter(Card::create_deck());
-        let mut board = Vec::new();
+    pub fn from_spec(spec: &GameSpec) -> Result<Game, GameSpecError> {
+        spec.validate()?;
+        let variant = spec.game.variant();
+        let mut undealt = variant.deck();
+        let mut board = Vec::with_capacity(variant.board_size());
         let mut hole_cards = Vec::new();
 
         // Set up board
-        for spec_card in &spec.board {
-            let card = cards_set.take(&spec_card).unwrap();
-            board.push(card);
+        for &spec_card in &spec.board {
+            take_card(&mut undealt, spec_card)?;
+            board.push(spec_card);
+        }
+
+        // Take out the dead cards
+        for &dead_card in &spec.dead_cards {
+            take_card(&mut undealt, dead_card)?;
         }
 
-        // Set up hole cards
-        for (spec_card_1, spec_card_2) in &spec.hole_cards {
-            let card_1 = cards_set.take(&spec_card_1).unwrap();
-            let card_2 = cards_set.take(&spec_card_2).unwrap();
-            hole_cards.push((card_1, card_2));
+        // Set up known hole cards. Everyone else gets only the cards we know of, or a
+        // placeholder hand from their range, until `deal_hole_cards` is called.
+        let num_hole_cards = variant.num_hole_cards();
+        let mut unknown_seats = Vec::new();
+        for (player, seat) in spec.seats.iter().enumerate() {
+            let mut player_cards = Vec::with_capacity(num_hole_cards);
+            match seat {
+                Seat::Known(_) | Seat::Stud { .. } => {
+                    for spec_card in seat.known_cards() {
+                        take_card(&mut undealt, spec_card)?;
+                        player_cards.push(spec_card);
+                    }
+                    if player_cards.len() < num_hole_cards {
+                        unknown_seats.push((player, player_cards.len()));
+                    }
+                }
+                Seat::Range(range) => {
+                    let (card_1, card_2) = range.combos()[0].0;
+                    player_cards.extend([card_1, card_2]);
+                }
+                Seat::Random => unknown_seats.push((player, 0)),
+            }
+            hole_cards.push(player_cards);
         }
 
-        // Put remaining cards into deck and shuffle
-        let mut deck: Vec<Card> = cards_set.into_iter().collect();
-        deck.shuffle(&mut thread_rng());
+        // Set up ranges, without the hands that clash with the known cards
+        let known_cards = CardSet::full_deck().difference(undealt);
+        let seat_ranges = spec
+            .seats
+            .iter()
+            .map(|seat| match seat {
+                Seat::Range(range) => Some(SeatRange::new(range, known_cards)),
+                _ => None,
+            })
+            .collect();
 
-        Game {
-            deck,
+        Ok(Game {
+            variant,
+            undealt,
+            deck: undealt,
+            num_spec_board_cards: board.len(),
             board,
             hole_cards,
-        }
+            seat_ranges,
+            dealt_combos: vec![None; spec.seats.len()],
+            unknown_seats,
+        })
     }
 
-    pub fn deal_down_to_river(&mut self) {
-        let cards_to_deal = 5 - self.board.len();
-        for _ in 0..cards_to_deal {
-            self.board.push(self.deck.pop().unwrap());
+    pub fn reset(&mut self) {
+        self.deck = self.undealt;
+        self.board.truncate(self.num_spec_board_cards);
+    }
+
+    /// Deals each player given a range a hand from it, and then the players whose cards are
+    /// unknown the rest of their cards from what's left of the deck.
+    ///
+    /// The range hands are dealt one player at a time, each picked at random in proportion to
+    /// the weights from the hands that don't clash with those already dealt. That deals hands
+    /// which clash with other players' ranges more often than the joint distribution of the
+    /// ranges would, so the deal is returned with a weight that corrects for it: the product,
+    /// over the players given a range, of the fraction of their range's weight that was left to
+    /// pick from. Trials should count in proportion to it. The weight is 1 whenever no hand
+    /// clashed. If some player is left with no hand at all, the deal is started again.
+    pub fn deal_hole_cards<R: Rng + ?Sized>(&mut self, rng: &mut R) -> f64 {
+        let mut weight;
+        'deal: loop {
+            weight = 1.;
+            let mut dealt = CardSet::empty();
+            for (player, seat_range) in self.seat_ranges.iter().enumerate() {
+                if let Some(seat_range) = seat_range {
+                    let Some((index, fraction)) = seat_range.sample_avoiding(dealt, rng) else {
+                        continue 'deal;
+                    };
+                    weight *= fraction;
+                    let (card_1, card_2) = seat_range.combos[index];
+                    dealt.insert(card_1);
+                    dealt.insert(card_2);
+                    self.hole_cards[player].clear();
+                    self.hole_cards[player].extend([card_1, card_2]);
+                    self.dealt_combos[player] = Some(index);
+                }
+            }
+            self.deck = self.deck.difference(dealt);
+            break;
+        }
+
+        let num_hole_cards = self.variant.num_hole_cards();
+        for &(player, num_known_cards) in &self.unknown_seats {
+            let hole_cards = &mut self.hole_cards[player];
+            hole_cards.truncate(num_known_cards);
+            while hole_cards.len() < num_hole_cards {
+                hole_cards.push(take_random_card(&mut self.deck, rng));
+            }
         }
+        weight
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.hole_cards.len()
+    }
+
+    /// The combos that a player given a range can be dealt, or None for any other player.
+    pub fn range_combos(&self, player: usize) -> Option<&[Combo]> {
+        self.seat_ranges[player]
+            .as_ref()
+            .map(|seat_range| seat_range.combos.as_slice())
     }
 
-    pub fn get_player_hands(&self) -> Vec<Hand> {
+    /// For a player given a range, the index into `range_combos` of the hand they were dealt.
+    pub fn dealt_combo(&self, player: usize) -> Option<usize> {
+        self.dealt_combos[player]
+    }
+
+    pub fn deal_down_to_river<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let runout = self.deck.sample(self.num_cards_to_come(), rng);
+        self.deal_runout(runout);
+    }
+
+    /// The number of board cards still to be dealt.
+    pub fn num_cards_to_come(&self) -> usize {
+        self.variant.board_size() - self.board.len()
+    }
+
+    /// The cards that haven't been dealt to the board or to a player.
+    pub fn deck(&self) -> CardSet {
+        self.deck
+    }
+
+    /// Completes the board with a particular set of cards from the deck, rather than random
+    /// ones. Used to enumerate every possible runout.
+    pub fn deal_runout(&mut self, runout: CardSet) {
+        debug_assert_eq!(runout.count(), self.num_cards_to_come());
+        debug_assert!(runout.difference(self.deck).is_empty());
+        self.deck = self.deck.difference(runout);
+        self.board.extend(runout);
+    }
+
+    pub fn get_player_hands(&self) -> Vec<Hand<'_>> {
         (0..self.hole_cards.len())
             .map(|player| self.get_scoring_hand_for_player(player))
             .collect()
     }
 
-    pub fn get_winning_players_and_hands(&self) -> Vec<(usize, Hand)> {
+    /// The players holding the strongest high hand, who split the pot, or just its high half if
+    /// somebody has a low. Empty in razz, which is played for low only.
+    pub fn get_winning_players_and_hands(&self) -> Vec<(usize, Hand<'_>)> {
+        if self.variant.pot_split() == PotSplit::Low {
+            return Vec::new();
+        }
         let player_hands = self.get_player_hands();
         let best_hand = player_hands.iter().max().unwrap().clone();
-        let winning_players_and_hands: Vec<(usize, Hand)> = player_hands
+        let winning_players_and_hands: Vec<(usize, Hand<'_>)> = player_hands
             .into_iter()
             .enumerate()
             .filter(|(_, hand)| *hand == best_hand)
@@ -74,20 +805,724 @@ impl Game {
         winning_players_and_hands
     }
 
-    fn get_scoring_hand_for_player(&self, player: usize) -> Hand {
-        // Out of the 7 cards that can be used in a hand (2 hole cards plus 5 board cards), get
-        // every possible 5 card hand combination, then find the highest-scoring hand.
-        let mut all_cards: Vec<&Card> = Vec::new();
-        all_cards.push(&self.hole_cards[player].0);
-        all_cards.push(&self.hole_cards[player].1);
-        all_cards.append(&mut self.board.iter().collect());
+    /// Each player's best low hand, or None if they don't have one that qualifies. All None in
+    /// games played for high only.
+    pub fn get_player_lows(&self) -> Vec<Option<LowStrength>> {
+        let board: CardSet = self.board.iter().copied().collect();
+        (0..self.hole_cards.len())
+            .map(|player| self.get_low_for_player(player, board))
+            .collect()
+    }
+
+    /// The players holding the best qualifying low hand, who split the low half of the pot.
+    /// Empty if nobody has a low, in which case the high hand takes the whole pot.
+    pub fn get_winning_low_players_and_hands(&self) -> Vec<(usize, LowStrength)> {
+        let player_lows = self.get_player_lows();
+        let Some(best_low) = player_lows.iter().max().copied().flatten() else {
+            return Vec::new();
+        };
+        player_lows
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, low)| low == Some(best_low))
+            .map(|(player, _)| (player, best_low))
+            .collect()
+    }
+
+    /// Fills `winners` with the players holding the strongest high hand, or leaves it empty in
+    /// razz. This is the fast path used by the simulations: it only scores the hands rather than
+    /// building them, and reuses the caller's vector so it doesn't allocate.
+    pub fn get_winning_players(&self, winners: &mut Vec<usize>) {
+        winners.clear();
+        if self.variant.pot_split() == PotSplit::Low {
+            return;
+        }
+        let board: CardSet = self.board.iter().copied().collect();
+        let mut best_strength = None;
+        for player in 0..self.hole_cards.len() {
+            let strength = Some(self.get_strength_for_player(player, board));
+            if strength > best_strength {
+                best_strength = strength;
+                winners.clear();
+            }
+            if strength == best_strength {
+                winners.push(player);
+            }
+        }
+    }
+
+    /// Fills `winners` with the players holding the best qualifying low hand, or leaves it empty
+    /// if nobody has one or the game is played for high only. Like `get_winning_players`, it
+    /// doesn't allocate.
+    pub fn get_winning_low_players(&self, winners: &mut Vec<usize>) {
+        winners.clear();
+        if self.variant.pot_split() == PotSplit::High {
+            return;
+        }
+        let board: CardSet = self.board.iter().copied().collect();
+        let mut best_low = None;
+        for player in 0..self.hole_cards.len() {
+            let low = self.get_low_for_player(player, board);
+            if low > best_low {
+                best_low = low;
+                winners.clear();
+            }
+            if low.is_some() && low == best_low {
+                winners.push(player);
+            }
+        }
+    }
+
+    /// Whether the board plays for everyone: the five board cards make every player's best hand,
+    /// so nobody's hole cards matter and the pot is split between them all. Always false until
+    /// the board is complete, and in games like Omaha where hole cards must be used.
+    pub fn board_plays(&self) -> bool {
+        if self.board.len() < HAND_SIZE || self.variant.hole_cards_used().is_some() {
+            return false;
+        }
+        let board: CardSet = self.board.iter().copied().collect();
+        let board_strength = evaluate_with_ranking(board, self.variant.ranking());
+        (0..self.hole_cards.len())
+            .all(|player| self.get_strength_for_player(player, board) == board_strength)
+    }
+
+    fn get_strength_for_player(&self, player: usize, board: CardSet) -> HandStrength {
+        let hole_cards: CardSet = self.hole_cards[player].iter().copied().collect();
+        self.variant.best_hand(hole_cards, board)
+    }
+
+    fn get_low_for_player(&self, player: usize, board: CardSet) -> Option<LowStrength> {
+        let hole_cards: CardSet = self.hole_cards[player].iter().copied().collect();
+        self.variant.best_low(hole_cards, board)
+    }
+
+    // The variant picks the cards of the player's best hand, which are then built into a `Hand`
+    // from the player's and the board's cards.
+    fn get_scoring_hand_for_player(&self, player: usize) -> Hand<'_> {
+        let hole_cards = &self.hole_cards[player];
+        let board: CardSet = self.board.iter().copied().collect();
+        let hole_card_set: CardSet = hole_cards.iter().copied().collect();
+        let hand_cards = self.variant.best_hand_cards(hole_card_set, board);
+        let cards = hole_cards
+            .iter()
+            .chain(&self.board)
+            .filter(|card| hand_cards.contains(**card))
+            .collect();
+        Hand::with_ranking(cards, self.variant.ranking())
+    }
+}
+
+fn take_card(cards_set: &mut CardSet, card: Card) -> Result<(), GameSpecError> {
+    if cards_set.remove(card) {
+        Ok(())
+    } else {
+        Err(GameSpecError::DuplicateCard(card))
+    }
+}
+
+fn take_random_card<R: Rng + ?Sized>(deck: &mut CardSet, rng: &mut R) -> Card {
+    deck.deal(rng)
+        .expect("Validation ensures there are enough cards to deal everyone")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+    use crate::hands::HandType;
+
+    // Each hand is parsed as a seat: cards, a range or "random"
+    fn make_spec(board: &str, hands: &[&str]) -> GameSpec {
+        GameSpec {
+            board: parse_cards(board).unwrap(),
+            seats: hands.iter().map(|hand| hand.parse().unwrap()).collect(),
+            dead_cards: Vec::new(),
+            game: GameType::Holdem,
+        }
+    }
+
+    fn make_game_spec(game: GameType, board: &str, hands: &[&str]) -> GameSpec {
+        GameSpec {
+            game,
+            ..make_spec(board, hands)
+        }
+    }
+
+    #[test]
+    fn test_winning_players_match_winning_hands() {
+        let spec = make_spec("Qs Kd Jc", &["Qh Qd", "Ac As", "Tc 9c"]);
+        let mut game = Game::from_spec(&spec).unwrap();
+        let mut rng = rand::thread_rng();
+        let mut winners = Vec::new();
+        for _ in 0..1000 {
+            game.reset();
+            game.deal_down_to_river(&mut rng);
+            game.get_winning_players(&mut winners);
+            let expected: Vec<usize> = game
+                .get_winning_players_and_hands()
+                .into_iter()
+                .map(|(player, _)| player)
+                .collect();
+            assert_eq!(winners, expected);
+        }
+    }
+
+    #[test]
+    fn test_deal_hole_cards_from_ranges() {
+        let spec = make_spec("As Kd 7h", &["AA, KK, AKs", "AhKh", "QQ+"]);
+        let mut game = Game::from_spec(&spec).unwrap();
+        // Only AdAc, KsKc and AcKc are left once the board and AhKh are taken out
+        assert_eq!(game.range_combos(0).unwrap().len(), 3);
+        assert_eq!(game.range_combos(1), None);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            game.reset();
+            game.deal_hole_cards(&mut rng);
+            game.deal_down_to_river(&mut rng);
+            let mut all_cards: CardSet = game.board.iter().copied().collect();
+            for &card in game.hole_cards.iter().flatten() {
+                assert!(all_cards.insert(card));
+            }
+            let (c1, c2) = game.range_combos(2).unwrap()[game.dealt_combo(2).unwrap()];
+            assert_eq!(game.hole_cards[2], [c1, c2]);
+            assert_eq!(all_cards.count() + game.deck().count(), 52);
+        }
+    }
+
+    #[test]
+    fn test_deal_hole_cards_weights_clashing_ranges() {
+        let spec = make_spec("", &["AsAh, KsKh", "AsAd, QsQh", "random"]);
+        let mut game = Game::from_spec(&spec).unwrap();
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            game.reset();
+            let weight = game.deal_hole_cards(&mut rng);
+            // After AsAh the second player only has half their range left to be dealt from
+            let expected = if game.hole_cards[0][0] == "As".parse().unwrap() {
+                assert_eq!(game.hole_cards[1], parse_cards("Qs Qh").unwrap());
+                0.5
+            } else {
+                1.
+            };
+            assert_eq!(weight, expected);
+        }
+
+        // Ranges that can't clash are dealt with a weight of 1
+        let spec = make_spec("", &["AA", "KK", "random"]);
+        let mut game = Game::from_spec(&spec).unwrap();
+        assert_eq!(game.deal_hole_cards(&mut rng), 1.);
+    }
+
+    #[test]
+    fn test_deal_partial_and_random_hole_cards() {
+        let spec = make_spec("As Kd 7h", &["QQ", "Ah", "random", "Qh Qd"]);
+        let mut game = Game::from_spec(&spec).unwrap();
+        assert_eq!(game.range_combos(1), None);
+        assert_eq!(game.range_combos(2), None);
 
-        let scoring_hand = all_cards
+        let mut rng = rand::thread_rng();
+        let mut second_cards = CardSet::empty();
+        for _ in 0..1000 {
+            game.reset();
+            game.deal_hole_cards(&mut rng);
+            game.deal_down_to_river(&mut rng);
+            let mut all_cards: CardSet = game.board.iter().copied().collect();
+            for &card in game.hole_cards.iter().flatten() {
+                assert!(all_cards.insert(card));
+            }
+            assert_eq!(game.hole_cards[1][0], "Ah".parse().unwrap());
+            assert_eq!(game.hole_cards[0], parse_cards("Qs Qc").unwrap());
+            assert_eq!(all_cards.count() + game.deck().count(), 52);
+            second_cards.insert(game.hole_cards[1][1]);
+        }
+        // The second card is drawn from the whole deck, not always the same one
+        assert!(second_cards.count() > 20);
+    }
+
+    #[test]
+    fn test_against_random() {
+        let spec = GameSpec::against_random(Vec::new(), "AKs".parse().unwrap(), 5);
+        assert_eq!(spec.seats.len(), 6);
+        assert!(spec.seats[1..].iter().all(|seat| *seat == Seat::Random));
+        assert!(spec.has_unknown_hands());
+        assert_eq!(spec.validate(), Ok(()));
+        let spec = GameSpec::against_random(Vec::new(), "AsKs".parse().unwrap(), 23);
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyPlayers(24)));
+    }
+
+    #[test]
+    fn test_validate_partial_hand() {
+        let spec = make_spec("As Kd 7h", &["Kd", "random"]);
+        assert_eq!(
+            spec.validate(),
+            Err(GameSpecError::DuplicateCard("Kd".parse().unwrap()))
+        );
+        // The partial card blocks hands from other players' ranges
+        let spec = make_spec("As Ad 7h", &["Ah", "AA"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::EmptyRange(1)));
+    }
+
+    #[test]
+    fn test_validate_ranges() {
+        let spec = make_spec("As Ah Ad", &["AA", "KK"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::EmptyRange(0)));
+
+        let spec = make_spec("", &["AhAs, AdAc", "AhAd, AsAc", "KK"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::NoPossibleDeal));
+
+        let spec = make_spec("", &["AhAs, AdAc", "AhAs", "KK"]);
+        assert_eq!(spec.validate(), Ok(()));
+
+        // Ten players can split the five pairs between them, but not eleven. Both have to be
+        // answered without trying every way of dealing the pairs out.
+        let spec = make_spec("", &["AA, KK, QQ, JJ, TT"; 10]);
+        assert_eq!(spec.validate(), Ok(()));
+        let spec = make_spec("", &["AA, KK, QQ, JJ, TT"; 11]);
+        assert_eq!(spec.validate(), Err(GameSpecError::NoPossibleDeal));
+    }
+
+    #[test]
+    fn test_validate_ok() {
+        let spec = make_spec("Qs Kd Jc", &["Qh Qd", "Ac As"]);
+        assert_eq!(spec.validate(), Ok(()));
+        assert!(Game::from_spec(&spec).is_ok());
+    }
+
+    #[test]
+    fn test_validate_duplicate_board_and_hole_card() {
+        let spec = make_spec("Qs Kd Jc", &["Qs Qd", "Ac As"]);
+        assert_eq!(
+            spec.validate(),
+            Err(GameSpecError::DuplicateCard("Qs".parse().unwrap()))
+        );
+        assert!(Game::from_spec(&spec).is_err());
+    }
+
+    #[test]
+    fn test_validate_duplicate_between_players() {
+        let spec = make_spec("", &["Ah Kd", "Ac Kd"]);
+        assert_eq!(
+            spec.validate(),
+            Err(GameSpecError::DuplicateCard("Kd".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_validate_board_too_long() {
+        let spec = make_spec("2c 3c 4c 5c 6c 7c", &["Ah Kd", "Ac Ks"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::BoardTooLong(6)));
+    }
+
+    #[test]
+    fn test_validate_player_counts() {
+        let spec = make_spec("", &["Ah Kd"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::TooFewPlayers(1)));
+
+        let mut spec = make_spec("", &[]);
+        let deck = Card::create_deck();
+        spec.seats = deck
             .into_iter()
-            .combinations(5)
-            .map(|cards| Hand::new(cards))
-            .max()
-            .unwrap();
-        scoring_hand
+            .tuples()
+            .take(24)
+            .map(|(c1, c2)| Seat::Known(vec![c1, c2]))
+            .collect();
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyPlayers(24)));
+        spec.seats.truncate(12);
+        spec.game = GameType::Omaha;
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyPlayers(12)));
+        spec.seats.truncate(11);
+        assert_eq!(spec.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_and_display_seat() {
+        let seat: Seat = "AsKd".parse().unwrap();
+        assert_eq!(seat, Seat::Known(parse_cards("As Kd").unwrap()));
+        assert_eq!(seat.to_string(), "AsKd");
+        let seat: Seat = "AA, AKs".parse().unwrap();
+        assert!(matches!(seat, Seat::Range(_)));
+        assert_eq!(seat.to_string(), "AA, AKs");
+        let seat: Seat = "As".parse().unwrap();
+        assert_eq!(seat, Seat::Known(parse_cards("As").unwrap()));
+        assert_eq!(seat.to_string(), "As");
+        let seat: Seat = "As Kd Qh 2c".parse().unwrap();
+        assert_eq!(seat, Seat::Known(parse_cards("As Kd Qh 2c").unwrap()));
+        assert_eq!(seat.to_string(), "AsKdQh2c");
+        let seat: Seat = "As 2d/7c".parse().unwrap();
+        assert_eq!(
+            seat,
+            Seat::Stud {
+                down: parse_cards("As 2d").unwrap(),
+                up: parse_cards("7c").unwrap(),
+            }
+        );
+        assert_eq!(seat.to_string(), "As2d/7c");
+        let seat: Seat = "/Kh Qh".parse().unwrap();
+        assert_eq!(seat.to_string(), "/KhQh");
+        assert_eq!(seat.to_string().parse::<Seat>().unwrap(), seat);
+        assert_eq!(
+            "As/7x".parse::<Seat>(),
+            Err(RangeParseError::InvalidHand(String::from("As/7x")))
+        );
+        assert_eq!("Random".parse::<Seat>().unwrap(), Seat::Random);
+        assert_eq!(Seat::Random.to_string(), "random");
+        assert!("AsKx".parse::<Seat>().is_err());
+    }
+
+    #[test]
+    fn test_dead_cards_are_not_dealt() {
+        let mut spec = make_spec("Qs Kd Jc", &["Qh Qd", "Ac As"]);
+        spec.dead_cards = parse_cards("Tc Th").unwrap();
+        let game = Game::from_spec(&spec).unwrap();
+        assert_eq!(game.deck().count(), 52 - 3 - 4 - 2);
+        assert!(spec
+            .dead_cards
+            .iter()
+            .all(|&card| !game.deck().contains(card)));
+
+        spec.dead_cards = parse_cards("Qh").unwrap();
+        assert_eq!(
+            spec.validate(),
+            Err(GameSpecError::DuplicateCard("Qh".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_validate_dead_cards() {
+        let mut spec = make_spec("", &["AA", "Kh Kd"]);
+        spec.dead_cards = parse_cards("As Ah Ad").unwrap();
+        assert_eq!(spec.validate(), Err(GameSpecError::EmptyRange(0)));
+
+        spec.dead_cards = parse_cards("Kd").unwrap();
+        assert_eq!(
+            spec.validate(),
+            Err(GameSpecError::DuplicateCard("Kd".parse().unwrap()))
+        );
+
+        spec.dead_cards = CardSet::full_deck()
+            .iter()
+            .filter(|card| card.rank() < 13)
+            .collect();
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyDeadCards(44)));
+        spec.dead_cards.truncate(43);
+        assert_eq!(spec.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_board_plays() {
+        let game = Game::from_spec(&make_spec("As Ks Qs Js Ts", &["2c 3d", "Ah Ad"])).unwrap();
+        assert!(game.board_plays());
+        let game = Game::from_spec(&make_spec("9c 9d 9h 9s Ac", &["2c 3d", "Kh Kd"])).unwrap();
+        assert!(game.board_plays());
+        let game = Game::from_spec(&make_spec("9c 9d 9h 9s Kc", &["2c 3d", "Ah Kd"])).unwrap();
+        assert!(!game.board_plays());
+        let game = Game::from_spec(&make_spec("9c 9d 9h 9s", &["2c 3d", "4h 5d"])).unwrap();
+        assert!(!game.board_plays());
+    }
+
+    #[test]
+    fn test_validate_hole_card_counts() {
+        let spec = make_spec("", &["AsKdQh", "random"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyHoleCards(0)));
+        let spec = make_game_spec(GameType::Omaha, "", &["AsKdQh", "random"]);
+        assert_eq!(spec.validate(), Ok(()));
+        assert!(spec.has_unknown_hands());
+        let spec = make_game_spec(GameType::Omaha, "", &["AsKdQh2c", "Ad Kh 7c 7d 8c"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyHoleCards(1)));
+        let spec = make_game_spec(GameType::Omaha5, "", &["AsKdQh2c", "Ad Kh 7c 7d 8c"]);
+        assert_eq!(spec.validate(), Ok(()));
+        assert!(spec.has_unknown_hands());
+        let spec = make_game_spec(GameType::Omaha, "", &["AsKdQh2c", "AA"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::RangeNotSupported(1)));
+    }
+
+    #[test]
+    fn test_omaha_uses_two_hole_cards() {
+        // A lone heart doesn't make a flush, and four of a kind on the board can't be used.
+        let spec = make_game_spec(
+            GameType::Omaha,
+            "Ah Kh Qh Jh 2c",
+            &["Th 9s 4d 5s", "As Ks 7d 8d"],
+        );
+        let game = Game::from_spec(&spec).unwrap();
+        let hands = game.get_player_hands();
+        assert_eq!(hands[0].hand_type, HandType::Straight);
+        assert_eq!(hands[1].hand_type, HandType::TwoPair);
+        assert!(!game.board_plays());
+
+        let spec = make_game_spec(
+            GameType::Omaha,
+            "9c 9d 9h 9s Ac",
+            &["2c 3d 4h 5s", "Kh Kd 6c 7d"],
+        );
+        let game = Game::from_spec(&spec).unwrap();
+        let hands = game.get_player_hands();
+        assert_eq!(hands[0].hand_type, HandType::ThreeOfAKind);
+        assert_eq!(hands[1].hand_type, HandType::FullHouse);
+        let mut winners = Vec::new();
+        game.get_winning_players(&mut winners);
+        assert_eq!(winners, [1]);
+    }
+
+    #[test]
+    fn test_omaha_winning_players_match_winning_hands() {
+        for (game_type, hands) in [
+            (GameType::Omaha, ["Qh Qd 8c 9c", "Ac As 2d 3d", "random"]),
+            (GameType::Omaha5, ["Qh Qd 8c 9c 9h", "Ac As 2d", "random"]),
+            (GameType::Omaha6, ["Qh Qd 8c 9c 9h Th", "random", "random"]),
+        ] {
+            let spec = make_game_spec(game_type, "Qs Kd Jc", &hands);
+            let mut game = Game::from_spec(&spec).unwrap();
+            let mut rng = rand::thread_rng();
+            let mut winners = Vec::new();
+            for _ in 0..300 {
+                game.reset();
+                game.deal_hole_cards(&mut rng);
+                game.deal_down_to_river(&mut rng);
+                assert!(game
+                    .hole_cards
+                    .iter()
+                    .all(|cards| cards.len() == game_type.variant().num_hole_cards()));
+                game.get_winning_players(&mut winners);
+                let expected: Vec<usize> = game
+                    .get_winning_players_and_hands()
+                    .into_iter()
+                    .map(|(player, _)| player)
+                    .collect();
+                assert_eq!(winners, expected);
+                let strengths: Vec<HandStrength> = game
+                    .get_player_hands()
+                    .iter()
+                    .map(|hand| hand.strength())
+                    .collect();
+                let board: CardSet = game.board.iter().copied().collect();
+                for (player, strength) in strengths.into_iter().enumerate() {
+                    assert_eq!(game.get_strength_for_player(player, board), strength);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_game_type_counts() {
+        assert_eq!(GameType::Holdem.variant().hands_per_player(), 1);
+        assert_eq!(GameType::Omaha.variant().hands_per_player(), 60);
+        assert_eq!(GameType::Omaha5.variant().hands_per_player(), 100);
+        assert_eq!(GameType::Omaha6.variant().hands_per_player(), 150);
+        assert_eq!(GameType::OmahaHiLo.variant().hands_per_player(), 120);
+        assert_eq!(
+            serde_json::from_str::<GameType>(r#""plo5""#).unwrap(),
+            GameType::Omaha5
+        );
+        assert_eq!(
+            serde_json::from_str::<GameType>(r#""plo8""#).unwrap(),
+            GameType::OmahaHiLo
+        );
+        assert_eq!(
+            serde_json::to_string(&GameType::Omaha).unwrap(),
+            r#""omaha""#
+        );
+    }
+
+    #[test]
+    fn test_validate_short_deck() {
+        let spec = make_game_spec(GameType::ShortDeck, "As 5d 7h", &["Ah Kd", "Qc Qs"]);
+        assert_eq!(
+            spec.validate(),
+            Err(GameSpecError::CardNotInDeck("5d".parse().unwrap()))
+        );
+        // The low pairs and the cards on the board are blocked
+        let spec = make_game_spec(GameType::ShortDeck, "6s 6d 7h", &["22-66", "random"]);
+        let game = Game::from_spec(&spec).unwrap();
+        assert_eq!(game.range_combos(0).unwrap().len(), 1);
+        let spec = make_game_spec(GameType::ShortDeck, "", &["22-55", "random"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::EmptyRange(0)));
+
+        let mut spec = make_game_spec(GameType::ShortDeck, "", &["AhKh"]);
+        spec.seats.resize(16, Seat::Random);
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyPlayers(16)));
+        spec.seats.truncate(15);
+        assert_eq!(spec.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_short_deck_deals_from_short_deck() {
+        let spec = make_game_spec(GameType::ShortDeck, "", &["Ah Kd", "random", "random"]);
+        let mut game = Game::from_spec(&spec).unwrap();
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            game.reset();
+            game.deal_hole_cards(&mut rng);
+            game.deal_down_to_river(&mut rng);
+            let dealt = game.board.iter().chain(game.hole_cards.iter().flatten());
+            assert!(dealt.copied().all(|card| card.rank() >= 6));
+            assert_eq!(game.deck().count(), 36 - 5 - 6);
+        }
+    }
+
+    #[test]
+    fn test_omaha_hi_lo_lows() {
+        // A low also needs exactly two hole cards and three board cards
+        let spec = make_game_spec(
+            GameType::OmahaHiLo,
+            "5c 6d Kh Kc Ks",
+            &["Ah 2d 3c 4s", "8h 9h Ts Js"],
+        );
+        let game = Game::from_spec(&spec).unwrap();
+        assert_eq!(game.get_player_lows(), [None, None]);
+        assert!(game.get_winning_low_players_and_hands().is_empty());
+
+        let spec = make_game_spec(
+            GameType::OmahaHiLo,
+            "5c 6d 7h Kc Ks",
+            &["Ah 2d 3c 4s", "8h 9h Ts Js"],
+        );
+        let game = Game::from_spec(&spec).unwrap();
+        let lows = game.get_player_lows();
+        assert_eq!(lows[0].unwrap().describe(), "7-6 low (7-6-5-2-A)");
+        assert_eq!(lows[1], None);
+        let mut winners = Vec::new();
+        game.get_winning_low_players(&mut winners);
+        assert_eq!(winners, [0]);
+
+        // Games played for high only have no lows
+        let game = Game::from_spec(&make_spec("5c 6d 7h", &["Ah 2d", "3c 4s"])).unwrap();
+        game.get_winning_low_players(&mut winners);
+        assert!(winners.is_empty());
+    }
+
+    #[test]
+    fn test_winning_low_players_match_winning_low_hands() {
+        let spec = make_game_spec(
+            GameType::OmahaHiLo,
+            "2c 7d",
+            &["Ah 3d 4s Ks", "As 2h 8c 8d", "random"],
+        );
+        let mut game = Game::from_spec(&spec).unwrap();
+        let mut rng = rand::thread_rng();
+        let mut winners = Vec::new();
+        for _ in 0..1000 {
+            game.reset();
+            game.deal_hole_cards(&mut rng);
+            game.deal_down_to_river(&mut rng);
+            game.get_winning_low_players(&mut winners);
+            let winning_lows = game.get_winning_low_players_and_hands();
+            let expected: Vec<usize> = winning_lows.iter().map(|&(player, _)| player).collect();
+            assert_eq!(winners, expected);
+            assert!(winning_lows.iter().all(|(_, low)| low.is_at_most(8)));
+        }
+    }
+
+    #[test]
+    fn test_validate_stud() {
+        let spec = make_game_spec(GameType::Stud, "", &["As Kd 7h", "Qc Qs 2d"]);
+        assert_eq!(spec.validate(), Ok(()));
+        assert!(spec.has_unknown_hands());
+        let spec = make_game_spec(GameType::Razz, "Ah", &["As Kd 7h", "Qc Qs 2d"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::BoardTooLong(1)));
+        let spec = make_game_spec(GameType::StudHiLo, "", &["QQ+", "random"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::RangeNotSupported(0)));
+
+        let mut spec = make_game_spec(GameType::Stud, "", &["As Kd 7h"]);
+        spec.seats.resize(8, Seat::Random);
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyPlayers(8)));
+        spec.seats.truncate(7);
+        assert_eq!(spec.validate(), Ok(()));
+        spec.dead_cards = parse_cards("2c 3c 4c").unwrap();
+        assert_eq!(spec.validate(), Ok(()));
+        spec.dead_cards.push("5c".parse().unwrap());
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyDeadCards(4)));
+
+        // Down and up cards
+        let spec = make_game_spec(GameType::StudHiLo, "", &["As 2d 9c/7c 8c 3h Kd", "/Qs Qd"]);
+        assert_eq!(spec.validate(), Ok(()));
+        assert!(spec.has_unknown_hands());
+        let spec = make_game_spec(GameType::Stud, "", &["As 2d 9c 4s/7c", "/Qs Qd"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyDownCards(0)));
+        let spec = make_game_spec(GameType::Razz, "", &["As/7c 8c 3h Kd", "/Qs Qd 2h 3s 4d"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::TooManyUpCards(1)));
+        let spec = make_game_spec(GameType::Stud, "", &["As 2d/7c", "/Qs 7c"]);
+        assert_eq!(
+            spec.validate(),
+            Err(GameSpecError::DuplicateCard("7c".parse().unwrap()))
+        );
+        let spec = make_game_spec(GameType::Holdem, "", &["As/Kd", "random"]);
+        assert_eq!(spec.validate(), Err(GameSpecError::UpCardsNotSupported(0)));
+    }
+
+    #[test]
+    fn test_stud_deals_seven_cards_each() {
+        let mut spec = make_game_spec(GameType::Stud, "", &["As Kd 7h 7c", "Qc Qs 2d", "random"]);
+        spec.dead_cards = parse_cards("Ac Ad 9s").unwrap();
+        let mut game = Game::from_spec(&spec).unwrap();
+        assert_eq!(game.num_cards_to_come(), 0);
+        let mut rng = rand::thread_rng();
+        let mut winners = Vec::new();
+        for _ in 0..300 {
+            game.reset();
+            game.deal_hole_cards(&mut rng);
+            game.deal_down_to_river(&mut rng);
+            assert!(game.board.is_empty());
+            assert!(game.hole_cards.iter().all(|cards| cards.len() == 7));
+            assert_eq!(game.hole_cards[0][..4], parse_cards("As Kd 7h 7c").unwrap());
+            assert_eq!(game.hole_cards[1][..3], parse_cards("Qc Qs 2d").unwrap());
+            let dealt: CardSet = game.hole_cards.iter().flatten().copied().collect();
+            assert_eq!(dealt.count(), 21);
+            assert!(spec.dead_cards.iter().all(|&card| !dealt.contains(card)));
+
+            game.get_winning_players(&mut winners);
+            let expected: Vec<usize> = game
+                .get_winning_players_and_hands()
+                .into_iter()
+                .map(|(player, _)| player)
+                .collect();
+            assert_eq!(winners, expected);
+            game.get_winning_low_players(&mut winners);
+            assert!(winners.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_stud_deals_partially_known_down_and_up_cards() {
+        // The hero knows their own down cards, and only the opponent's up cards are showing
+        let mut spec = make_game_spec(GameType::Razz, "", &["As 2d/7c 5h", "/Kh 4s"]);
+        spec.dead_cards = parse_cards("3c 8d").unwrap();
+        let mut game = Game::from_spec(&spec).unwrap();
+        let mut rng = rand::thread_rng();
+        for _ in 0..300 {
+            game.reset();
+            game.deal_hole_cards(&mut rng);
+            assert!(game.hole_cards.iter().all(|cards| cards.len() == 7));
+            assert_eq!(game.hole_cards[0][..4], parse_cards("As 2d 7c 5h").unwrap());
+            assert_eq!(game.hole_cards[1][..2], parse_cards("Kh 4s").unwrap());
+            let dealt: CardSet = game.hole_cards.iter().flatten().copied().collect();
+            assert_eq!(dealt.count(), 14);
+            assert!(spec.dead_cards.iter().all(|&card| !dealt.contains(card)));
+        }
+    }
+
+    #[test]
+    fn test_razz_is_played_for_low() {
+        let spec = make_game_spec(
+            GameType::Razz,
+            "",
+            &[
+                "As 2d 3c 4h 5s Kd Kh",
+                "6c 6d 6h 6s 7c 8c 9c",
+                "Ac 2c 3d 4d Kc Qc Jc",
+            ],
+        );
+        let game = Game::from_spec(&spec).unwrap();
+        let mut winners = Vec::new();
+        game.get_winning_players(&mut winners);
+        assert!(winners.is_empty());
+        assert!(game.get_winning_players_and_hands().is_empty());
+        game.get_winning_low_players(&mut winners);
+        assert_eq!(winners, [0]);
+        // Every hand has a low in razz, paired or not
+        let lows = game.get_player_lows();
+        assert_eq!(lows[1].unwrap().describe(), "Pair (6-6-9-8-7)");
+        assert_eq!(lows[2].unwrap().describe(), "J-4 low (J-4-3-2-A)");
     }
 }
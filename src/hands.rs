@@ -1,15 +1,16 @@
-use crate::cards::Card;
+use crate::cards::{rank_name, rank_name_plural, Card};
+use crate::evaluator::HandStrength;
 use itertools::Itertools;
-use std::cmp::Ordering;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::fmt;
 
 // Logic for types of hands and their relative value
 
-// need to write a whole bunch of test cases, lots of edge tests
-// (e.g. multi-way straights, etc.)
-
 // We want HandType to be a thing that can be copied, not moved
 // i.e. ht1 = ht2 is a copy and means that both ht1 and ht2 remain valid.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum HandType {
     StraightFlush,
     Quads,
@@ -22,22 +23,137 @@ pub enum HandType {
     HighCard,
 }
 
+impl HandType {
+    pub fn from_level(level: u8) -> HandType {
+        match level {
+            9 => HandType::StraightFlush,
+            8 => HandType::Quads,
+            7 => HandType::FullHouse,
+            6 => HandType::Flush,
+            5 => HandType::Straight,
+            4 => HandType::ThreeOfAKind,
+            3 => HandType::TwoPair,
+            2 => HandType::Pair,
+            1 => HandType::HighCard,
+            _ => panic!("Invalid hand level!"),
+        }
+    }
+
+    /// How strong the hand type is, from 1 for high card up to 9 for a straight flush.
+    pub fn level(&self) -> u8 {
+        match self {
+            HandType::StraightFlush => 9,
+            HandType::Quads => 8,
+            HandType::FullHouse => 7,
+            HandType::Flush => 6,
+            HandType::Straight => 5,
+            HandType::ThreeOfAKind => 4,
+            HandType::TwoPair => 3,
+            HandType::Pair => 2,
+            HandType::HighCard => 1,
+        }
+    }
+}
+
+impl fmt::Display for HandType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HandType::StraightFlush => "Straight flush",
+            HandType::Quads => "Four of a kind",
+            HandType::FullHouse => "Full house",
+            HandType::Flush => "Flush",
+            HandType::Straight => "Straight",
+            HandType::ThreeOfAKind => "Three of a kind",
+            HandType::TwoPair => "Two pair",
+            HandType::Pair => "Pair",
+            HandType::HighCard => "High card",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Hand types are ordered by how they rank in standard poker, so a flush is greater than a
+// straight. Under other rankings, compare `HandRanking::order` instead.
+impl Ord for HandType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let ranking = HandRanking::Standard;
+        ranking.order(*self).cmp(&ranking.order(*other))
+    }
+}
+
+impl PartialOrd for HandType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The order in which hand types rank, along with the lowest straight. Standard hands rank as
+/// usual with A-2-3-4-5 the lowest straight. Short-deck hold'em is played without the twos to
+/// fives, which makes flushes rarer than full houses, so a flush beats a full house and
+/// A-6-7-8-9 is the lowest straight. Some short-deck games also rank three of a kind above a
+/// straight.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandRanking {
+    #[default]
+    Standard,
+    ShortDeck,
+    ShortDeckTripsBeatStraight,
+}
+
+impl HandRanking {
+    /// Where a hand type comes in this ranking, from 1 for the weakest up to 9 for the
+    /// strongest. For standard hands this is just the level.
+    pub fn order(&self, hand_type: HandType) -> u8 {
+        match (self, hand_type) {
+            (HandRanking::Standard, _) => hand_type.level(),
+            (_, HandType::FullHouse) => 6,
+            (_, HandType::Flush) => 7,
+            (HandRanking::ShortDeckTripsBeatStraight, HandType::Straight) => 4,
+            (HandRanking::ShortDeckTripsBeatStraight, HandType::ThreeOfAKind) => 5,
+            _ => hand_type.level(),
+        }
+    }
+
+    pub fn is_short_deck(&self) -> bool {
+        *self != HandRanking::Standard
+    }
+
+    pub fn trips_beat_straight(&self) -> bool {
+        *self == HandRanking::ShortDeckTripsBeatStraight
+    }
+
+    // The ranks of the lowest straight, in order, and its score: the rank the ace plays as.
+    fn low_straight(&self) -> ([u8; 5], u64) {
+        match self {
+            HandRanking::Standard => ([2, 3, 4, 5, 14], 1),
+            _ => ([6, 7, 8, 9, 14], 5),
+        }
+    }
+}
+
 // Since `Hand` contains a vector of references to cards, it needs a lifetime specifier.
 // This says: for a `Hand` with an associated lifetime 'a, we guarantee that the associated
 // lifetimes of the Card references will each live at least as long as the Hand lifetime 'a.
 // That is to say, the references to the Cards must refer to valid existing things
 // for as long as the Hand exists.
-#[derive(Clone, Eq)]
+#[derive(Debug, Clone, Eq, Serialize)]
 pub struct Hand<'a> {
     pub cards: Vec<&'a Card>,
     pub hand_type: HandType,
+    #[serde(skip)]
     level: u8,
+    #[serde(skip)]
     score: u64,
+    #[serde(skip)]
+    ranking: HandRanking,
 }
 
+// Hands compare by strength, which takes their ranking into account. Hands ranked differently
+// shouldn't be compared.
 impl<'a> Ord for Hand<'a> {
     fn cmp(&self, other: &Self) -> Ordering {
-        (self.level, self.score).cmp(&(other.level, other.score))
+        self.strength().cmp(&other.strength())
     }
 }
 
@@ -49,33 +165,276 @@ impl<'a> PartialOrd for Hand<'a> {
 
 impl<'a> PartialEq for Hand<'a> {
     fn eq(&self, other: &Self) -> bool {
-        (self.level, self.score).eq(&(other.level, other.score))
+        self.strength().eq(&other.strength())
     }
 }
 
 impl<'a> Hand<'a> {
-    pub fn new(cards: Vec<&Card>) -> Hand {
-        let (hand_type, level, score) = get_hand_type_level_and_score(&cards);
+    pub fn new(cards: Vec<&'a Card>) -> Hand<'a> {
+        Hand::with_ranking(cards, HandRanking::Standard)
+    }
+
+    /// A hand ranked by the given rules rather than the standard ones.
+    pub fn with_ranking(cards: Vec<&'a Card>, ranking: HandRanking) -> Hand<'a> {
+        let (hand_type, level, score) = get_hand_type_level_and_score(&cards, ranking);
         Hand {
             cards,
             hand_type,
             level,
             score,
+            ranking,
         }
     }
 
-    // todo: do printing better, impl Display instead
-    #[allow(dead_code)]
-    pub fn print_hand(&self) {
-        // Q - why do we need the & on &self below?
+    pub fn strength(&self) -> HandStrength {
+        HandStrength::ranked(self.ranking, self.level, self.score)
+    }
+
+    /// The cards ordered by how much they matter to the hand: the biggest group of cards of one
+    /// rank first, then the next biggest and so on, with higher ranks first among groups of the
+    /// same size. Straights run from the top card down, so a wheel (or A-6-7-8-9 in short deck)
+    /// ends with its ace.
+    pub fn ordered_cards(&self) -> Vec<&'a Card> {
+        // The score of a straight is the rank of its low card, which is only below ten when the
+        // ace plays low
+        let ace_low = self.is_straight() && self.score < 10;
+        let effective_rank = |card: &Card| match card.rank() {
+            14 if ace_low => self.score as u8,
+            rank => rank,
+        };
+        let mut rank_counts = [0; 15];
         for card in &self.cards {
-            print!("{} ", card);
+            rank_counts[card.rank() as usize] += 1;
         }
-        println!("");
-        println!(
-            "Hand type: {:?}, level: {}, score: {}",
-            &self.hand_type, &self.level, &self.score
-        );
+        let mut ordered_cards = self.cards.clone();
+        ordered_cards
+            .sort_by_key(|card| Reverse((rank_counts[card.rank() as usize], effective_rank(card))));
+        ordered_cards
+    }
+
+    /// Describes the hand in words, e.g. "Full house, Kings full of Jacks" or "Two pair, Aces
+    /// and Threes with a King kicker".
+    pub fn describe(&self) -> String {
+        let ranks: Vec<u8> = self
+            .ordered_cards()
+            .iter()
+            .map(|card| card.rank())
+            .dedup()
+            .collect();
+        let detail = match self.hand_type {
+            HandType::StraightFlush if ranks[0] == 14 => return "Royal flush".to_string(),
+            HandType::StraightFlush | HandType::Straight if self.score == 1 => {
+                "Five-high (wheel)".to_string()
+            }
+            HandType::StraightFlush | HandType::Straight | HandType::Flush => {
+                format!("{}-high", rank_name(ranks[0]))
+            }
+            HandType::Quads => format!(
+                "{} with {}",
+                rank_name_plural(ranks[0]),
+                describe_kickers(&ranks[1..])
+            ),
+            HandType::FullHouse => format!(
+                "{} full of {}",
+                rank_name_plural(ranks[0]),
+                rank_name_plural(ranks[1])
+            ),
+            HandType::ThreeOfAKind | HandType::Pair | HandType::HighCard => format!(
+                "{} with {}",
+                rank_name_plural_or_single(self.hand_type, ranks[0]),
+                describe_kickers(&ranks[1..])
+            ),
+            HandType::TwoPair => format!(
+                "{} and {} with {}",
+                rank_name_plural(ranks[0]),
+                rank_name_plural(ranks[1]),
+                describe_kickers(&ranks[2..])
+            ),
+        };
+        format!("{}, {}", self.hand_type, detail)
+    }
+
+    /// The ranks that decide between two hands of the same type, most significant first: each
+    /// group of cards of the same rank, then the kickers. A straight only has its top card, which
+    /// for a wheel is the five (and for A-6-7-8-9 the nine).
+    pub fn significant_ranks(&self) -> Vec<u8> {
+        if self.is_straight() {
+            return vec![self.score as u8 + 4];
+        }
+        self.ordered_cards()
+            .iter()
+            .map(|card| card.rank())
+            .dedup()
+            .collect()
+    }
+
+    fn is_straight(&self) -> bool {
+        matches!(self.hand_type, HandType::Straight | HandType::StraightFlush)
+    }
+}
+
+/// Which part of two hands decided the showdown between them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Decider {
+    /// One hand is a better type of hand, e.g. a flush beats a straight.
+    HandType,
+    /// The main rank of the hand: the pair, the top pair of two pair, the three or four of a
+    /// kind, the trips of a full house, or the top card of a straight, flush or high card hand.
+    PrimaryRank,
+    /// The second pair of two pair, or the pair of a full house.
+    SecondaryRank,
+    /// The n-th kicker, counting from 1. The cards of a flush after the first count as kickers.
+    Kicker(usize),
+    /// Nothing: the hands are equal and the pot is split.
+    Tie,
+}
+
+/// The result of `compare_explained`: how the first hand compares to the second, what decided
+/// it, and a sentence saying so.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandComparison {
+    pub ordering: Ordering,
+    pub decider: Decider,
+    pub explanation: String,
+}
+
+/// Compares two hands like `cmp`, but also says why one beats the other, e.g. "Two pair, Aces
+/// and Threes with a King kicker beats two pair, Aces and Twos with a King kicker on the second
+/// pair (Threes beat Twos)".
+pub fn compare_explained(first: &Hand, second: &Hand) -> HandComparison {
+    let ordering = first.cmp(second);
+    let (winner, loser) = match ordering {
+        Ordering::Less => (second, first),
+        _ => (first, second),
+    };
+    if ordering == Ordering::Equal {
+        return HandComparison {
+            ordering,
+            decider: Decider::Tie,
+            explanation: format!(
+                "{} ties with {}, so the pot is split",
+                first.describe(),
+                lowercase_first(&second.describe())
+            ),
+        };
+    }
+
+    let beats = format!(
+        "{} beats {}",
+        winner.describe(),
+        lowercase_first(&loser.describe())
+    );
+    if winner.hand_type != loser.hand_type {
+        return HandComparison {
+            ordering,
+            decider: Decider::HandType,
+            explanation: format!(
+                "{} ({} beats {})",
+                beats,
+                lowercase_first(&winner.hand_type.to_string()),
+                lowercase_first(&loser.hand_type.to_string())
+            ),
+        };
+    }
+
+    // Same type, so the ranks are compared in order of significance until one differs
+    let (index, winning_rank, losing_rank) = winner
+        .significant_ranks()
+        .into_iter()
+        .zip(loser.significant_ranks())
+        .enumerate()
+        .map(|(index, (winning_rank, losing_rank))| (index, winning_rank, losing_rank))
+        .find(|(_, winning_rank, losing_rank)| winning_rank != losing_rank)
+        .expect("Unequal hands of the same type differ in some rank");
+    let (decider, component, is_group) = describe_component(winner.hand_type, index);
+    let ranks = if is_group {
+        format!(
+            "{} beat {}",
+            rank_name_plural(winning_rank),
+            rank_name_plural(losing_rank)
+        )
+    } else {
+        format!(
+            "{} beats {}",
+            rank_name(winning_rank),
+            rank_name(losing_rank)
+        )
+    };
+    HandComparison {
+        ordering,
+        decider,
+        explanation: format!("{} on the {} ({})", beats, component, ranks),
+    }
+}
+
+// What the rank at `index` in `significant_ranks` is for a type of hand: which decider it is, what
+// to call it, and whether it is a group of cards (so its rank is named in the plural).
+fn describe_component(hand_type: HandType, index: usize) -> (Decider, &'static str, bool) {
+    const KICKERS: [&str; 4] = ["kicker", "second kicker", "third kicker", "fourth kicker"];
+    let num_groups = match hand_type {
+        HandType::Quads | HandType::ThreeOfAKind | HandType::Pair => 1,
+        HandType::FullHouse | HandType::TwoPair => 2,
+        _ => 0,
+    };
+    match (hand_type, index) {
+        (HandType::TwoPair, 0) => (Decider::PrimaryRank, "top pair", true),
+        (HandType::TwoPair, 1) => (Decider::SecondaryRank, "second pair", true),
+        (HandType::FullHouse, 0) => (Decider::PrimaryRank, "three of a kind", true),
+        (HandType::FullHouse, 1) => (Decider::SecondaryRank, "pair", true),
+        (HandType::Quads, 0) => (Decider::PrimaryRank, "four of a kind", true),
+        (HandType::ThreeOfAKind, 0) => (Decider::PrimaryRank, "three of a kind", true),
+        (HandType::Pair, 0) => (Decider::PrimaryRank, "pair", true),
+        (_, 0) => (Decider::PrimaryRank, "high card", false),
+        // A single kicker is just "the kicker"; with more they are numbered
+        (HandType::Quads | HandType::TwoPair, _) => (Decider::Kicker(1), KICKERS[0], false),
+        (_, index) => {
+            let kicker = index + 1 - num_groups.max(1);
+            let name = if kicker == 1 {
+                "first kicker"
+            } else {
+                KICKERS[kicker - 1]
+            };
+            (Decider::Kicker(kicker), name, false)
+        }
+    }
+}
+
+fn lowercase_first(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// The rank a hand is named after: plural for a group of cards, e.g. "Kings", and singular for a
+// high card, e.g. "King".
+fn rank_name_plural_or_single(hand_type: HandType, rank: u8) -> String {
+    match hand_type {
+        HandType::HighCard => rank_name(rank).to_string(),
+        _ => rank_name_plural(rank),
+    }
+}
+
+// e.g. "a King kicker" or "Ace, Nine and Seven kickers".
+fn describe_kickers(ranks: &[u8]) -> String {
+    match ranks {
+        [rank] => format!("a {} kicker", rank_name(*rank)),
+        [rest @ .., last] => format!(
+            "{} and {} kickers",
+            rest.iter().map(|&rank| rank_name(rank)).join(", "),
+            rank_name(*last)
+        ),
+        [] => panic!("No kickers to describe!"),
+    }
+}
+
+/// Shows the cards in order of significance followed by a description, e.g.
+/// "Kh Kd Ks Jc Jd (Full house, Kings full of Jacks)".
+impl<'a> fmt::Display for Hand<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cards = self.ordered_cards().iter().join(" ");
+        write!(f, "{} ({})", cards, self.describe())
     }
 }
 
@@ -84,10 +443,11 @@ impl<'a> Hand<'a> {
 /// how strong the "Hand Type" is, e.g. Flush = 6 is stronger than Straight = 5. The "score" is a
 /// number that depends on the Hand Type and can be compared within hands of the same type to
 /// determine the stronger hand.
+/// The level doesn't depend on the ranking; how the levels are ordered does (see `HandRanking`).
 #[rustfmt::skip]
-fn get_hand_type_level_and_score(cards: &Vec<&Card>) -> (HandType, u8, u64) {
+fn get_hand_type_level_and_score(cards: &Vec<&Card>, ranking: HandRanking) -> (HandType, u8, u64) {
     let all_same_suit = all_same_suit(cards);
-    let straight_score = straight_score(cards);
+    let straight_score = straight_score(cards, ranking);
     let (groupings, groupings_score) = get_groupings_and_score(cards);
 
     match (groupings.as_slice(), all_same_suit, straight_score) {
@@ -114,11 +474,11 @@ fn get_groupings_and_score(cards: &Vec<&Card>) -> (Vec<u8>, u64) {
     const BASE: u64 = 16;
 
     let mut ordered_cards = cards.to_vec();
-    ordered_cards.sort_by_key(|card| card.rank);
+    ordered_cards.sort_by_key(|card| card.rank());
 
     let mut group_sizes_and_ranks: Vec<(u8, u8)> = ordered_cards
         .iter()
-        .chunk_by(|card| card.rank)
+        .chunk_by(|card| card.rank())
         .into_iter()
         .map(|(rank, chunk)| (chunk.count() as u8, rank))
         .collect();
@@ -138,19 +498,20 @@ fn get_groupings_and_score(cards: &Vec<&Card>) -> (Vec<u8>, u64) {
 }
 
 fn all_same_suit(cards: &Vec<&Card>) -> bool {
-    let first_suit = cards[0].suit.clone();
-    cards.iter().all(|card| card.suit == first_suit)
+    let first_suit = cards[0].suit();
+    cards.iter().all(|card| card.suit() == first_suit)
 }
 
-fn straight_score(cards: &Vec<&Card>) -> Option<u64> {
+fn straight_score(cards: &Vec<&Card>, ranking: HandRanking) -> Option<u64> {
     // special scoring function for straights or straight flushes
     // the score is the rank of the low card of the straight
-    let mut ordered_ranks: Vec<u8> = cards.iter().map(|card| card.rank).collect();
+    let mut ordered_ranks: Vec<u8> = cards.iter().map(|card| card.rank()).collect();
     ordered_ranks.sort();
 
-    // ace-2-3-4-5 "the wheel" special case
-    if ordered_ranks.as_slice() == [2, 3, 4, 5, 14] {
-        return Some(1);
+    // ace-2-3-4-5 "the wheel" special case, or ace-6-7-8-9 in short deck
+    let (low_straight, low_straight_score) = ranking.low_straight();
+    if ordered_ranks.as_slice() == low_straight {
+        return Some(low_straight_score);
     }
 
     let lowest = ordered_ranks[0];
@@ -168,11 +529,11 @@ mod tests {
     fn make_cards(cards_str: &str) -> Vec<Card> {
         cards_str
             .split_whitespace()
-            .map(|card_str| Card::from_str(card_str))
+            .map(|card_str| card_str.parse().unwrap())
             .collect()
     }
 
-    fn make_hand<'a>(cards: &'a Vec<Card>) -> Hand<'a> {
+    fn make_hand<'a>(cards: &'a [Card]) -> Hand<'a> {
         Hand::new(cards.iter().collect())
     }
 
@@ -302,7 +663,40 @@ mod tests {
         assert!(make_hand(q_1) == make_hand(q_2));
     }
 
-    // ... todo: more tests
+    #[test]
+    fn test_full_house_vs_full_house() {
+        let fh_1 = &make_cards("3h 3s 3c 2c 2d");
+        let fh_2 = &make_cards("2h 2s 2c Ac Ad");
+        assert!(make_hand(fh_1) > make_hand(fh_2));
+    }
+
+    #[test]
+    fn test_full_house_vs_full_house_pair() {
+        let fh_1 = &make_cards("Kh Ks Kc 9c 9d");
+        let fh_2 = &make_cards("Kh Ks Kd 8c 8d");
+        assert!(make_hand(fh_1) > make_hand(fh_2));
+    }
+
+    #[test]
+    fn test_full_house_vs_full_house_equal() {
+        let fh_1 = &make_cards("Kh Ks Kc 9c 9d");
+        let fh_2 = &make_cards("Kh Kd Kc 9h 9s");
+        assert!(make_hand(fh_1) == make_hand(fh_2));
+    }
+
+    #[test]
+    fn test_full_house_vs_flush() {
+        let fh = &make_cards("2h 2s 2c 3c 3d");
+        let fl = &make_cards("Ah Kh Qh Jh 9h");
+        assert!(make_hand(fh) > make_hand(fl));
+    }
+
+    #[test]
+    fn test_flush_versus_flush() {
+        let fl_1 = &make_cards("Ad 7d 5d 4d 2d");
+        let fl_2 = &make_cards("Ks Qs Js 9s 8s");
+        assert!(make_hand(fl_1) > make_hand(fl_2));
+    }
 
     #[test]
     fn test_flush_versus_flush_lower_cards() {
@@ -311,7 +705,33 @@ mod tests {
         assert!(make_hand(fl_1) > make_hand(fl_2));
     }
 
-    // ... todo: more tests
+    #[test]
+    fn test_flush_versus_flush_equal() {
+        let fl_1 = &make_cards("Ks Js Ts 7s 5s");
+        let fl_2 = &make_cards("Kh Jh Th 7h 5h");
+        assert!(make_hand(fl_1) == make_hand(fl_2));
+    }
+
+    #[test]
+    fn test_flush_versus_straight() {
+        let fl = &make_cards("7c 5c 4c 3c 2c");
+        let st = &make_cards("Ac Kd Qh Js Tc");
+        assert!(make_hand(fl) > make_hand(st));
+    }
+
+    #[test]
+    fn test_straight_versus_straight() {
+        let st_1 = &make_cards("Ac Kd Qh Js Tc");
+        let st_2 = &make_cards("9c Kd Qh Js Tc");
+        assert!(make_hand(st_1) > make_hand(st_2));
+    }
+
+    #[test]
+    fn test_straight_versus_straight_equal() {
+        let st_1 = &make_cards("9c 8d 7h 6s 5c");
+        let st_2 = &make_cards("9d 8c 7s 6h 5d");
+        assert!(make_hand(st_1) == make_hand(st_2));
+    }
 
     #[test]
     fn test_straight_versus_wheel_straight() {
@@ -320,7 +740,68 @@ mod tests {
         assert!(make_hand(q_1) > make_hand(q_2));
     }
 
-    // ... todo: more tests
+    #[test]
+    fn test_wheel_straight_vs_three_of_a_kind() {
+        let st = &make_cards("Ac 2c 3h 4d 5c");
+        let t = &make_cards("Ac Ad As Kd Qc");
+        assert!(make_hand(st) > make_hand(t));
+    }
+
+    #[test]
+    fn test_three_of_a_kind_vs_three_of_a_kind() {
+        let t_1 = &make_cards("3c 3d 3s 4d 2c");
+        let t_2 = &make_cards("2h 2d 2s Ad Kc");
+        assert!(make_hand(t_1) > make_hand(t_2));
+    }
+
+    #[test]
+    fn test_three_of_a_kind_vs_three_of_a_kind_kicker() {
+        let t_1 = &make_cards("9c 9d 9s Kd 3c");
+        let t_2 = &make_cards("9c 9h 9s Kd 2c");
+        assert!(make_hand(t_1) > make_hand(t_2));
+    }
+
+    #[test]
+    fn test_three_of_a_kind_vs_two_pair() {
+        let t = &make_cards("2c 2d 2s 4d 3c");
+        let tp = &make_cards("Ac Ad Kd Kc Qh");
+        assert!(make_hand(t) > make_hand(tp));
+    }
+
+    #[test]
+    fn test_two_pair_vs_two_pair() {
+        let tp_1 = &make_cards("Ac Ad 2d 2c 3h");
+        let tp_2 = &make_cards("Kc Kd Qd Qc Jh");
+        assert!(make_hand(tp_1) > make_hand(tp_2));
+    }
+
+    #[test]
+    fn test_two_pair_vs_two_pair_second_pair() {
+        let tp_1 = &make_cards("Ac Ad 4d 4c 2h");
+        let tp_2 = &make_cards("Ah As 3d 3c Kh");
+        assert!(make_hand(tp_1) > make_hand(tp_2));
+    }
+
+    #[test]
+    fn test_two_pair_vs_two_pair_kicker() {
+        let tp_1 = &make_cards("Ac Ad 4d 4c 6h");
+        let tp_2 = &make_cards("Ah As 4h 4s 5h");
+        assert!(make_hand(tp_1) > make_hand(tp_2));
+    }
+
+    #[test]
+    fn test_two_pair_vs_two_pair_equal() {
+        let tp_1 = &make_cards("Ac Ad 4d 4c 6h");
+        let tp_2 = &make_cards("Ah As 4h 4s 6c");
+        assert!(make_hand(tp_1) == make_hand(tp_2));
+    }
+
+    #[test]
+    fn test_two_pair_vs_pair() {
+        let tp = &make_cards("3c 3d 2d 2c 4h");
+        let p = &make_cards("Ac Ad Kd Qc Jh");
+        assert!(make_hand(tp) > make_hand(p));
+    }
 
     #[test]
     fn test_pair_vs_pair() {
@@ -343,10 +824,244 @@ mod tests {
         assert!(make_hand(p) > make_hand(hc));
     }
 
+    #[test]
+    fn test_pair_vs_pair_equal() {
+        let p_1 = &make_cards("6s Js Th 6d 4h");
+        let p_2 = &make_cards("6c Jd Tc 6h 4d");
+        assert!(make_hand(p_1) == make_hand(p_2));
+    }
+
     #[test]
     fn test_high_card_vs_high_card() {
         let hc_1 = &make_cards("Ac Jc Ks 6d 4h");
         let hc_2 = &make_cards("Ad Js Ks 6d 2h");
         assert!(make_hand(hc_1) > make_hand(hc_2));
     }
+
+    #[test]
+    fn test_high_card_vs_high_card_equal() {
+        let hc_1 = &make_cards("Ac Jc Ks 6d 4h");
+        let hc_2 = &make_cards("Ad Jd Kc 6h 4s");
+        assert!(make_hand(hc_1) == make_hand(hc_2));
+    }
+
+    #[test]
+    fn test_hand_type_order_and_level() {
+        assert!(HandType::StraightFlush > HandType::Quads);
+        assert!(HandType::Flush > HandType::Straight);
+        assert!(HandType::Pair > HandType::HighCard);
+        for level in 1..=9 {
+            assert_eq!(HandType::from_level(level).level(), level);
+        }
+    }
+
+    #[test]
+    fn test_serialize_hand() {
+        let cards = make_cards("Jh Jd Js 7d 7c");
+        let hand = make_hand(&cards);
+        assert_eq!(
+            serde_json::to_string(&hand).unwrap(),
+            r#"{"cards":["Jh","Jd","Js","7d","7c"],"hand_type":"full_house"}"#
+        );
+        let hand_type: HandType = serde_json::from_str(r#""three_of_a_kind""#).unwrap();
+        assert_eq!(hand_type, HandType::ThreeOfAKind);
+    }
+
+    #[test]
+    fn test_describe() {
+        let cases = [
+            ("Jh Th Ah Kh Qh", "Royal flush"),
+            ("9h Th Jh Kh Qh", "Straight flush, King-high"),
+            ("5d 2d Ad 3d 4d", "Straight flush, Five-high (wheel)"),
+            (
+                "Jh Jd Js Jc 7d",
+                "Four of a kind, Jacks with a Seven kicker",
+            ),
+            ("Jd Kh Ks Jc Kd", "Full house, Kings full of Jacks"),
+            ("3h 2h Ah 5h 9h", "Flush, Ace-high"),
+            ("8d 7h 9d 6s Th", "Straight, Ten-high"),
+            ("5d 2s Ah 3d 4c", "Straight, Five-high (wheel)"),
+            (
+                "2s 2h Kh Qs 2c",
+                "Three of a kind, Twos with King and Queen kickers",
+            ),
+            (
+                "As 3c Ah Kd 3h",
+                "Two pair, Aces and Threes with a King kicker",
+            ),
+            (
+                "6s 6c 7s Ad 9d",
+                "Pair, Sixes with Ace, Nine and Seven kickers",
+            ),
+            (
+                "2s Qh 7c Kd 8d",
+                "High card, King with Queen, Eight, Seven and Two kickers",
+            ),
+        ];
+        for (cards_str, description) in cases {
+            let cards = make_cards(cards_str);
+            assert_eq!(make_hand(&cards).describe(), description, "{}", cards_str);
+        }
+    }
+
+    #[test]
+    fn test_ordered_cards() {
+        let cases = [
+            ("Jd Kh Ks Jc Kd", "Kh Ks Kd Jd Jc"),
+            ("As 3c Ah Kd 3h", "As Ah 3c 3h Kd"),
+            ("5d 2s Ah 3d 4c", "5d 4c 3d 2s Ah"),
+            ("2s Qh 7c Kd 8d", "Kd Qh 8d 7c 2s"),
+        ];
+        for (cards_str, ordered) in cases {
+            let cards = make_cards(cards_str);
+            let hand = make_hand(&cards);
+            assert_eq!(hand.ordered_cards().iter().join(" "), ordered);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let cards = make_cards("Jd Kh Ks Jc Kd");
+        assert_eq!(
+            make_hand(&cards).to_string(),
+            "Kh Ks Kd Jd Jc (Full house, Kings full of Jacks)"
+        );
+        assert_eq!(HandType::ThreeOfAKind.to_string(), "Three of a kind");
+    }
+
+    #[test]
+    fn test_compare_explained() {
+        let cases = [
+            (
+                "Jd Kh Ks Jc Kd",
+                "3h 2h Ah 5h 9h",
+                Decider::HandType,
+                "Full house, Kings full of Jacks beats flush, Ace-high (full house beats flush)",
+            ),
+            (
+                "As 3c Ah Kd 3h",
+                "Ac 2c Ad Kc 2h",
+                Decider::SecondaryRank,
+                "Two pair, Aces and Threes with a King kicker beats two pair, Aces and Twos with \
+                 a King kicker on the second pair (Threes beat Twos)",
+            ),
+            (
+                "Ks Kc 9s 8d 3d",
+                "Kh Kd 9h 8c 2d",
+                Decider::Kicker(3),
+                "Pair, Kings with Nine, Eight and Three kickers beats pair, Kings with Nine, \
+                 Eight and Two kickers on the third kicker (Three beats Two)",
+            ),
+            (
+                "6d 7h 8d 9s Th",
+                "5d 2s Ah 3d 4c",
+                Decider::PrimaryRank,
+                "Straight, Ten-high beats straight, Five-high (wheel) on the high card (Ten \
+                 beats Five)",
+            ),
+            (
+                "Jh Jd Js Jc 7d",
+                "Jh Jd Js Jc 6d",
+                Decider::Kicker(1),
+                "Four of a kind, Jacks with a Seven kicker beats four of a kind, Jacks with a \
+                 Six kicker on the kicker (Seven beats Six)",
+            ),
+        ];
+        for (winner_str, loser_str, decider, explanation) in cases {
+            let winner_cards = make_cards(winner_str);
+            let loser_cards = make_cards(loser_str);
+            let (winner, loser) = (make_hand(&winner_cards), make_hand(&loser_cards));
+            let comparison = compare_explained(&winner, &loser);
+            assert_eq!(comparison.ordering, Ordering::Greater);
+            assert_eq!(comparison.decider, decider);
+            assert_eq!(comparison.explanation, explanation);
+            let reversed = compare_explained(&loser, &winner);
+            assert_eq!(reversed.ordering, Ordering::Less);
+            assert_eq!(reversed.decider, decider);
+            assert_eq!(reversed.explanation, explanation);
+        }
+    }
+
+    #[test]
+    fn test_compare_explained_tie() {
+        let first_cards = make_cards("As Ks Qs Js 9d");
+        let second_cards = make_cards("Ah Kh Qh Jh 9c");
+        let comparison = compare_explained(&make_hand(&first_cards), &make_hand(&second_cards));
+        assert_eq!(comparison.ordering, Ordering::Equal);
+        assert_eq!(comparison.decider, Decider::Tie);
+        assert_eq!(
+            comparison.explanation,
+            "High card, Ace with King, Queen, Jack and Nine kickers ties with high card, Ace \
+             with King, Queen, Jack and Nine kickers, so the pot is split"
+        );
+    }
+
+    #[test]
+    fn test_short_deck_flush_beats_full_house() {
+        let fl = &make_cards("Ah Kh 9h 7h 6h");
+        let fh = &make_cards("Kc Kd Ks 6c 6d");
+        assert!(make_hand(fl) < make_hand(fh));
+        for ranking in [
+            HandRanking::ShortDeck,
+            HandRanking::ShortDeckTripsBeatStraight,
+        ] {
+            let fl = Hand::with_ranking(fl.iter().collect(), ranking);
+            let fh = Hand::with_ranking(fh.iter().collect(), ranking);
+            assert!(fl > fh);
+            assert_eq!(
+                compare_explained(&fl, &fh).explanation,
+                "Flush, Ace-high beats full house, Kings full of Sixes (flush beats full house)"
+            );
+        }
+    }
+
+    #[test]
+    fn test_short_deck_low_straight() {
+        let cards = &make_cards("9h 6c Ah 8s 7d");
+        assert_eq!(make_hand(cards).hand_type, HandType::HighCard);
+
+        let low_straight = Hand::with_ranking(cards.iter().collect(), HandRanking::ShortDeck);
+        assert_eq!(low_straight.hand_type, HandType::Straight);
+        assert_eq!(low_straight.describe(), "Straight, Nine-high");
+        assert_eq!(low_straight.significant_ranks(), [9]);
+        let ordered: Vec<String> = low_straight
+            .ordered_cards()
+            .iter()
+            .map(|card| card.to_string())
+            .collect();
+        assert_eq!(ordered, ["9h", "8s", "7d", "6c", "Ah"]);
+
+        let next_straight = &make_cards("Th 6c 9h 8s 7d");
+        let next_straight =
+            Hand::with_ranking(next_straight.iter().collect(), HandRanking::ShortDeck);
+        assert!(next_straight > low_straight);
+        let trips = &make_cards("Ah Ad As Kd Qc");
+        let trips = Hand::with_ranking(trips.iter().collect(), HandRanking::ShortDeck);
+        assert!(low_straight > trips);
+
+        let ranking = HandRanking::ShortDeckTripsBeatStraight;
+        let low_straight = Hand::with_ranking(cards.iter().collect(), ranking);
+        let trips = &make_cards("6h 6d 6s 7d 8c");
+        let trips = Hand::with_ranking(trips.iter().collect(), ranking);
+        assert!(trips > low_straight);
+        assert_eq!(
+            compare_explained(&trips, &low_straight).decider,
+            Decider::HandType
+        );
+    }
+
+    #[test]
+    fn test_hand_ranking_order() {
+        for ranking in [
+            HandRanking::Standard,
+            HandRanking::ShortDeck,
+            HandRanking::ShortDeckTripsBeatStraight,
+        ] {
+            let orders: Vec<u8> = (1..=9)
+                .map(|level| ranking.order(HandType::from_level(level)))
+                .sorted()
+                .collect();
+            assert_eq!(orders, (1..=9).collect::<Vec<u8>>());
+        }
+    }
 }
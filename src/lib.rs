@@ -0,0 +1,38 @@
+//! Hand evaluation and equity calculation for Texas hold'em, Omaha, short-deck hold'em and
+//! seven-card stud, with their hi-lo and lowball (razz) variants.
+//!
+//! Describe a situation with a [`GameSpec`]: the [`GameType`], the board so far, and for each
+//! player a [`Seat`] saying what is known about their hole cards, whether all of them, some of
+//! them, a [`Range`] of hands they could hold or nothing at all. Then [`calculate_equity`] works
+//! out each player's share of the pot, either exactly or by simulation. The same calculations can
+//! be described and answered in JSON with an [`EquityRequest`].
+
+pub mod card_set;
+pub mod cards;
+pub mod equity;
+pub mod evaluator;
+pub mod game;
+pub mod hands;
+pub mod lowball;
+pub mod range;
+pub mod request;
+pub mod variant;
+
+pub use card_set::CardSet;
+pub use cards::{parse_cards, Card, CardParseError, ParseCardsError, Suit};
+pub use equity::{
+    calculate_equity, enumerate_equity, num_runouts, simulate_equity_from_game_spec,
+    simulate_equity_until, simulate_equity_until_with_rng, simulate_equity_with_rng, EquityConfig,
+    EquityMode, EquityResult, StoppingRule,
+};
+pub use evaluator::{
+    evaluate, evaluate_with_ranking, HandStrength, NUM_FIVE_CARD_HANDS, NUM_HAND_CLASSES,
+};
+pub use game::{GameSpec, GameSpecError, GameType, Seat};
+pub use hands::{compare_explained, Decider, Hand, HandComparison, HandRanking, HandType};
+pub use lowball::{
+    evaluate_ace_to_five, evaluate_deuce_to_seven, evaluate_low, LowRules, LowStrength,
+};
+pub use range::{Combo, Range, RangeParseError};
+pub use request::{respond_to_json, EquityRequest, EquityResponse, Settings, MAX_REQUEST_THREADS};
+pub use variant::{Holdem, Omaha, PotSplit, Stud, Variant};
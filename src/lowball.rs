@@ -0,0 +1,380 @@
+use crate::card_set::CardSet;
+use crate::cards::{rank_to_char, Card};
+use crate::hands::HandType;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+// Low hand evaluation, for lowball games and the low half of split pots.
+//
+// A low hand is ranked as a high hand turned upside down: the worse it would be for high, the
+// better it is for low. The two sets of rules differ in what counts against a hand:
+//
+// - Ace-to-five: aces are low and straights and flushes don't count, so the best hand is
+//   5-4-3-2-A. Only pairs and other groups of cards make a hand worse. Used in razz and in hi-lo
+//   split games.
+// - Deuce-to-seven: aces are high and straights and flushes count, so the best hand is
+//   7-5-4-3-2 in at least two suits. A-2-3-4-5 is just ace-high.
+//
+// The value of five cards for high is packed like a `HandStrength`, with the level of the hand
+// type above the ranks of each group of cards, biggest group first. A `LowStrength` flips that
+// over, so that a better low hand compares greater.
+
+const SCORE_BITS: u32 = 20;
+const MAX_HIGH_VALUE: u32 = (10 << SCORE_BITS) - 1;
+
+/// The rules for ranking low hands.
+#[derive(
+    Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LowRules {
+    /// Aces low, with straights and flushes ignored.
+    #[default]
+    AceToFive,
+    /// Aces high, with straights and flushes counting against the hand.
+    DeuceToSeven,
+}
+
+/// The value of a hand for low. A better low hand compares greater and equal values split, just
+/// like `HandStrength` does for high. Values under different rules shouldn't be compared.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LowStrength {
+    value: u32,
+    rules: LowRules,
+}
+
+impl LowStrength {
+    pub fn rules(&self) -> LowRules {
+        self.rules
+    }
+
+    /// The type of the hand as a high hand, e.g. a pair. Under ace-to-five rules this is never a
+    /// straight or a flush.
+    pub fn hand_type(&self) -> HandType {
+        HandType::from_level(((MAX_HIGH_VALUE - self.value) >> SCORE_BITS) as u8)
+    }
+
+    /// The ranks of the five cards from the most significant down: any groups of cards first,
+    /// then the rest from the highest. Under ace-to-five rules an ace is 1, so it comes last.
+    pub fn ranks(&self) -> Vec<u8> {
+        let group_sizes: &[usize] = match self.hand_type() {
+            HandType::Quads => &[4, 1],
+            HandType::FullHouse => &[3, 2],
+            HandType::ThreeOfAKind => &[3, 1, 1],
+            HandType::TwoPair => &[2, 2, 1],
+            HandType::Pair => &[2, 1, 1, 1],
+            _ => &[1, 1, 1, 1, 1],
+        };
+        let score = (MAX_HIGH_VALUE - self.value) & ((1 << SCORE_BITS) - 1);
+        group_sizes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &size)| {
+                let rank = (score >> (4 * (group_sizes.len() - 1 - i))) & 0xf;
+                std::iter::repeat_n(rank as u8, size)
+            })
+            .collect()
+    }
+
+    /// Whether this is a low without any pairs (or, under deuce-to-seven rules, straights or
+    /// flushes) whose highest card is no higher than `rank`, e.g. 8 for an eight-or-better low.
+    pub fn is_at_most(&self, rank: u8) -> bool {
+        self.hand_type() == HandType::HighCard && self.ranks()[0] <= rank
+    }
+
+    /// Describes the hand the way it's spoken of in lowball games, e.g. "7-5 low (7-5-4-3-A)",
+    /// "Number one (7-5-4-3-2)" for the best deuce-to-seven hand, or "Pair (6-6-7-4-2)".
+    pub fn describe(&self) -> String {
+        let ranks = self.ranks();
+        let cards = ranks.iter().map(|&rank| low_rank_char(rank)).join("-");
+        match self.hand_type() {
+            HandType::HighCard
+                if self.rules == LowRules::DeuceToSeven && ranks == [7, 5, 4, 3, 2] =>
+            {
+                format!("Number one ({})", cards)
+            }
+            HandType::HighCard => format!(
+                "{}-{} low ({})",
+                low_rank_char(ranks[0]),
+                low_rank_char(ranks[1]),
+                cards
+            ),
+            hand_type => format!("{} ({})", hand_type, cards),
+        }
+    }
+}
+
+// Aces low are stored as 1, but are still written "A".
+fn low_rank_char(rank: u8) -> char {
+    match rank {
+        1 => 'A',
+        rank => rank_to_char(rank),
+    }
+}
+
+/// Scores the best ace-to-five low hand that can be made from a set of 5, 6 or 7 cards.
+pub fn evaluate_ace_to_five(cards: CardSet) -> LowStrength {
+    evaluate_low(cards, LowRules::AceToFive)
+}
+
+/// Scores the best deuce-to-seven low hand that can be made from a set of 5, 6 or 7 cards.
+pub fn evaluate_deuce_to_seven(cards: CardSet) -> LowStrength {
+    evaluate_low(cards, LowRules::DeuceToSeven)
+}
+
+/// Scores the best low hand under the given rules that can be made from a set of 5, 6 or 7
+/// cards.
+pub fn evaluate_low(cards: CardSet, rules: LowRules) -> LowStrength {
+    debug_assert!((5..=7).contains(&cards.count()));
+    let best_value = match unpaired_low_value(cards, rules) {
+        Some(value) => value,
+        None if cards.count() == 5 => high_value(cards, rules),
+        None => cards
+            .combinations(5)
+            .map(|hand| high_value(hand, rules))
+            .min()
+            .unwrap(),
+    };
+    LowStrength {
+        value: MAX_HIGH_VALUE - best_value,
+        rules,
+    }
+}
+
+// Under ace-to-five rules any five different ranks make a plain low, so with at least five of
+// them the best hand is just the lowest five. That's the common case in hi-lo games and razz, so
+// it's scored straight from the ranks without looking at each five-card hand.
+fn unpaired_low_value(cards: CardSet, rules: LowRules) -> Option<u32> {
+    if rules != LowRules::AceToFive {
+        return None;
+    }
+    let mut ranks = cards.iter().fold(0u16, |ranks, card| {
+        ranks | 1 << if card.rank() == 14 { 1 } else { card.rank() }
+    });
+    if ranks.count_ones() < 5 {
+        return None;
+    }
+    while ranks.count_ones() > 5 {
+        ranks &= !(1 << (15 - ranks.leading_zeros()));
+    }
+    let score = (1..=14u8)
+        .rev()
+        .filter(|&rank| ranks & 1 << rank != 0)
+        .fold(0, |score, rank| (score << 4) | rank as u32);
+    Some(((HandType::HighCard.level() as u32) << SCORE_BITS) | score)
+}
+
+// The value of exactly five cards as a high hand under the given low rules: the level of the
+// hand type followed by the rank of each group of cards, four bits each.
+fn high_value(cards: CardSet, rules: LowRules) -> u32 {
+    let rank = |card: Card| match (rules, card.rank()) {
+        (LowRules::AceToFive, 14) => 1,
+        (_, rank) => rank,
+    };
+    let mut rank_counts = [0u8; 15];
+    for card in cards.iter() {
+        rank_counts[rank(card) as usize] += 1;
+    }
+    let groups: Vec<(u8, u8)> = (1..=14u8)
+        .filter(|&rank| rank_counts[rank as usize] > 0)
+        .map(|rank| (rank_counts[rank as usize], rank))
+        .sorted_by_key(|&group| Reverse(group))
+        .collect();
+    let score = groups
+        .iter()
+        .fold(0, |score, &(_, rank)| (score << 4) | rank as u32);
+
+    let hand_type = match groups.as_slice() {
+        [(4, _), _] => HandType::Quads,
+        [(3, _), (2, _)] => HandType::FullHouse,
+        [(3, _), ..] => HandType::ThreeOfAKind,
+        [(2, _), (2, _), _] => HandType::TwoPair,
+        [(2, _), ..] => HandType::Pair,
+        _ if rules == LowRules::AceToFive => HandType::HighCard,
+        [(_, high), .., (_, low)] => {
+            let straight = high - low == 4;
+            let first_suit = cards.iter().next().unwrap().suit();
+            let flush = cards.iter().all(|card| card.suit() == first_suit);
+            match (straight, flush) {
+                (true, true) => HandType::StraightFlush,
+                (false, true) => HandType::Flush,
+                (true, false) => HandType::Straight,
+                (false, false) => HandType::HighCard,
+            }
+        }
+        _ => unreachable!("Five cards always make at least two groups"),
+    };
+    ((hand_type.level() as u32) << SCORE_BITS) | score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+    use std::collections::HashSet;
+
+    fn make_set(cards_str: &str) -> CardSet {
+        parse_cards(cards_str).unwrap().into_iter().collect()
+    }
+
+    fn ace_to_five(cards_str: &str) -> LowStrength {
+        evaluate_ace_to_five(make_set(cards_str))
+    }
+
+    fn deuce_to_seven(cards_str: &str) -> LowStrength {
+        evaluate_deuce_to_seven(make_set(cards_str))
+    }
+
+    #[test]
+    fn test_ace_to_five_order() {
+        let hands = [
+            "5h 4h 3h 2h Ah",
+            "6c 4d 3h 2s Ac",
+            "6c 5d 3h 2s Ac",
+            "7c 5d 4h 3s 2c",
+            "8c 7d 6h 5s 4c",
+            "Kc Qd Jh Ts 9c",
+            "Ac Ad 2h 3s 4c",
+            "2c 2d Kh Qs Jc",
+            "Kc Kd Qh Qs Jc",
+            "Ac Ad Ah 2s 3c",
+            "Kc Kd Kh Ks Qc",
+        ];
+        for pair in hands.windows(2) {
+            assert!(ace_to_five(pair[0]) > ace_to_five(pair[1]), "{:?}", pair);
+        }
+        // Straights and flushes don't count
+        assert_eq!(ace_to_five("5h 4h 3h 2h Ah"), ace_to_five("5c 4d 3h 2s Ah"));
+        assert_eq!(
+            ace_to_five("5h 4h 3h 2h Ah").hand_type(),
+            HandType::HighCard
+        );
+    }
+
+    #[test]
+    fn test_deuce_to_seven_order() {
+        let hands = [
+            "7c 5d 4h 3s 2c",
+            "7c 6d 4h 3s 2c",
+            "8c 5d 4h 3s 2c",
+            "Kc Qd Jh Ts 8c",
+            "5c 4d 3h 2s Ac",
+            "2c 2d 3h 4s 5c",
+            "6c 5d 4h 3s 2c",
+            "7h 5h 4h 3h 2h",
+            "Kc Kd Kh Ks Qc",
+            "6h 5h 4h 3h 2h",
+        ];
+        for pair in hands.windows(2) {
+            assert!(
+                deuce_to_seven(pair[0]) > deuce_to_seven(pair[1]),
+                "{:?}",
+                pair
+            );
+        }
+        assert_eq!(
+            deuce_to_seven("5c 4d 3h 2s Ac").hand_type(),
+            HandType::HighCard
+        );
+        assert_eq!(
+            deuce_to_seven("6c 5d 4h 3s 2c").hand_type(),
+            HandType::Straight
+        );
+        assert_eq!(
+            deuce_to_seven("7h 5h 4h 3h 2h").hand_type(),
+            HandType::Flush
+        );
+    }
+
+    #[test]
+    fn test_best_low_from_seven_cards() {
+        assert_eq!(
+            ace_to_five("Ah 2c 3d 4s Kh Kd 5c"),
+            ace_to_five("Ah 2c 3d 4s 5c")
+        );
+        // Pairs can't always be avoided, and then the lowest one plays
+        let low = ace_to_five("Ah Ad 2c 2d 3h 3s 4c");
+        assert_eq!(low, ace_to_five("Ah Ad 2c 3h 4c"));
+        assert_eq!(low.describe(), "Pair (A-A-4-3-2)");
+        assert_eq!(
+            deuce_to_seven("7h 5h 4h 3h 2h Kd 8c"),
+            deuce_to_seven("8c 5h 4h 3h 2h")
+        );
+    }
+
+    #[test]
+    fn test_describe_low() {
+        assert_eq!(
+            ace_to_five("7c 5d 4h 3s Ac").describe(),
+            "7-5 low (7-5-4-3-A)"
+        );
+        assert_eq!(
+            ace_to_five("5c 4d 3h 2s Ac").describe(),
+            "5-4 low (5-4-3-2-A)"
+        );
+        assert_eq!(
+            deuce_to_seven("7c 5d 4h 3s 2c").describe(),
+            "Number one (7-5-4-3-2)"
+        );
+        assert_eq!(
+            ace_to_five("7c 5d 4h 3s 2c").describe(),
+            "7-5 low (7-5-4-3-2)"
+        );
+        assert_eq!(
+            deuce_to_seven("Kc 9d 5h 3s 2c").describe(),
+            "K-9 low (K-9-5-3-2)"
+        );
+        assert_eq!(ace_to_five("6c 6d 7h 4s 2c").describe(), "Pair (6-6-7-4-2)");
+        assert_eq!(
+            deuce_to_seven("9h 8h 7h 6h 5h").describe(),
+            "Straight flush (9-8-7-6-5)"
+        );
+        assert_eq!(
+            ace_to_five("Kc Kd Ks 2h 2c").describe(),
+            "Full house (K-K-K-2-2)"
+        );
+    }
+
+    #[test]
+    fn test_is_at_most() {
+        assert!(ace_to_five("8c 7d 6h 5s 4c").is_at_most(8));
+        assert!(ace_to_five("8c 7d 3h 2s Ac").is_at_most(8));
+        assert!(!ace_to_five("9c 5d 4h 3s 2c").is_at_most(8));
+        assert!(!ace_to_five("3c 3d 4h 5s 2c").is_at_most(8));
+        assert!(!deuce_to_seven("8c 7d 6h 5s 4c").is_at_most(8));
+    }
+
+    #[test]
+    fn test_all_five_card_lows() {
+        let mut ace_to_five_values = HashSet::new();
+        let mut deuce_to_seven_values = HashSet::new();
+        for cards in CardSet::full_deck().combinations(5) {
+            ace_to_five_values.insert(evaluate_ace_to_five(cards));
+            deuce_to_seven_values.insert(evaluate_deuce_to_seven(cards));
+        }
+        // Without straights and flushes there are 1287 unpaired lows and 4888 paired ones.
+        // Deuce-to-seven has as many values as high hands do.
+        assert_eq!(ace_to_five_values.len(), 6175);
+        assert_eq!(deuce_to_seven_values.len(), 7462);
+    }
+
+    #[test]
+    fn test_best_low_matches_every_five_card_hand() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10000 {
+            let cards = CardSet::full_deck().sample(7, &mut rng);
+            for rules in [LowRules::AceToFive, LowRules::DeuceToSeven] {
+                let best_value = cards
+                    .combinations(5)
+                    .map(|hand| high_value(hand, rules))
+                    .min()
+                    .unwrap();
+                assert_eq!(
+                    evaluate_low(cards, rules).value,
+                    MAX_HIGH_VALUE - best_value
+                );
+            }
+        }
+    }
+}
@@ -1,35 +1,285 @@
-mod cards;
-use cards::Card;
-mod equity;
-use equity::EquityResult;
-mod game;
-use game::GameSpec;
-mod hands;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
+use itertools::Itertools;
+use poker::{
+    parse_cards, respond_to_json, Card, EquityConfig, EquityMode, EquityResponse, EquityResult,
+    GameSpec, GameType, PotSplit, Seat,
+};
+use std::io::{self, BufRead, Write};
+
+/// Calculates each player's equity in a hold'em, Omaha, short-deck, stud or razz hand.
+///
+/// Examples: poker --board "Qs Kd Jc" QhQd "AA, AKs"
+///           poker --board "Qs Kd Jc" AKs --random 5
+///           poker --game omaha AhAd2c3d KsKh7s8s
+///           poker --game razz --dead "Kc 9h" "As 2d/7c" "/3h 4h Qs"
+///
+/// With --json, reads one JSON request per line from stdin and writes one JSON response per line
+/// to stdout, e.g. {"players": ["QhQd", "AA, AKs"], "board": ["Qs", "Kd", "Jc"]}. Requests can
+/// also give "dead_cards", "game" and "settings" with "mode", "simulations", "seed" and
+/// "threads".
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// Each player's hole cards, e.g. "AsKd", just some of them, e.g. "As", a range, e.g.
+    /// "QQ+, AKs, A5s-A2s", or "random". Ranges are only supported in hold'em games, including
+    /// short deck. In stud games, give the down and up cards a player is known to hold split by
+    /// a slash, e.g. "As2d/7c" or "/Kh", and the folded upcards as dead.
+    #[arg(required_unless_present = "json", value_parser = parse_seat)]
+    players: Vec<Seat>,
+
+    /// Add this many more players holding random hands.
+    #[arg(short, long, default_value_t = 0)]
+    random: usize,
+
+    /// The cards already on the board, e.g. "Qs Kd Jc".
+    #[arg(short, long, default_value = "", value_parser = parse_card_list)]
+    board: CardList,
+
+    /// Cards known to be out of play, e.g. folded hands that were shown.
+    #[arg(short, long, default_value = "", value_parser = parse_card_list)]
+    dead: CardList,
+
+    #[arg(short, long, value_enum, default_value_t = Game::Holdem)]
+    game: Game,
+
+    #[arg(short, long, value_enum, default_value_t = Mode::Auto)]
+    mode: Mode,
+
+    /// The number of trials to run when sampling.
+    #[arg(
+        short = 'n',
+        long,
+        default_value_t = 1000000,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    simulations: u64,
+
+    /// Seed for the random number generator, to reproduce an earlier run.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// The number of threads to simulate on. Defaults to one per CPU.
+    #[arg(short, long, value_parser = clap::value_parser!(u64).range(1..))]
+    threads: Option<u64>,
+
+    /// Answer JSON requests from stdin instead, one per line.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "players", "board", "dead", "random", "game", "mode", "simulations", "seed",
+            "threads"
+        ]
+    )]
+    json: bool,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Game {
+    /// Texas hold'em, with two hole cards.
+    Holdem,
+    /// Omaha, with four hole cards of which exactly two must be used.
+    #[value(alias = "plo", alias = "plo4")]
+    Omaha,
+    /// Five-card Omaha.
+    #[value(alias = "plo5")]
+    Omaha5,
+    /// Six-card Omaha.
+    #[value(alias = "plo6")]
+    Omaha6,
+    /// Omaha hi-lo, where the best eight-or-better low hand takes half the pot.
+    #[value(alias = "plo8", alias = "omaha8")]
+    OmahaHiLo,
+    /// Short-deck hold'em, without the twos to fives, where a flush beats a full house.
+    #[value(alias = "6plus")]
+    ShortDeck,
+    /// Short-deck hold'em where three of a kind also beats a straight.
+    ShortDeckTripsBeatStraight,
+    /// Seven-card stud, with no board and seven cards each.
+    #[value(alias = "stud-high")]
+    Stud,
+    /// Seven-card stud hi-lo, where the best eight-or-better low hand takes half the pot.
+    #[value(alias = "stud8")]
+    StudHiLo,
+    /// Razz, seven-card stud where the best ace-to-five low hand wins.
+    Razz,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Mode {
+    /// Enumerate every runout when there aren't too many, and sample otherwise.
+    Auto,
+    /// Enumerate every runout of the board.
+    Exact,
+    /// Sample random runouts.
+    MonteCarlo,
+}
+
+// A list of cards given as a single argument. clap would take a plain Vec field to mean the
+// argument is repeated.
+#[derive(Clone)]
+struct CardList(Vec<Card>);
+
+fn parse_seat(s: &str) -> Result<Seat, String> {
+    s.parse().map_err(|error| format!("{}", error))
+}
+
+fn parse_card_list(s: &str) -> Result<CardList, String> {
+    parse_cards(s)
+        .map(CardList)
+        .map_err(|error| format!("{}", error))
+}
 
 fn main() {
-    let mut board = Vec::new();
-    board.push(Card::from_str("Qs"));
-    board.push(Card::from_str("Kd"));
-    board.push(Card::from_str("Jc"));
-    board.push(Card::from_str("Tc"));
-    let mut hole_cards = Vec::new();
-    hole_cards.push((Card::from_str("Qh"), Card::from_str("Qd")));
-    hole_cards.push((Card::from_str("Ac"), Card::from_str("As")));
-    let game_spec = GameSpec { board, hole_cards };
-
-    let results = equity::simulate_equity_from_game_spec(game_spec, Some(1000000));
-    for (
-        player,
-        EquityResult {
-            equity,
-            win_percentage,
-            draw_percentage,
-        },
-    ) in results.iter().enumerate()
-    {
+    let cli = Cli::parse();
+    if cli.json {
+        std::process::exit(answer_json_requests());
+    }
+    let game = match cli.game {
+        Game::Holdem => GameType::Holdem,
+        Game::Omaha => GameType::Omaha,
+        Game::Omaha5 => GameType::Omaha5,
+        Game::Omaha6 => GameType::Omaha6,
+        Game::OmahaHiLo => GameType::OmahaHiLo,
+        Game::ShortDeck => GameType::ShortDeck,
+        Game::ShortDeckTripsBeatStraight => GameType::ShortDeckTripsBeatStraight,
+        Game::Stud => GameType::Stud,
+        Game::StudHiLo => GameType::StudHiLo,
+        Game::Razz => GameType::Razz,
+    };
+    let mode = match cli.mode {
+        Mode::Auto => EquityMode::Auto,
+        Mode::Exact => EquityMode::Exact,
+        Mode::MonteCarlo => EquityMode::MonteCarlo,
+    };
+    let mut config = EquityConfig {
+        seed: cli.seed,
+        ..EquityConfig::default()
+    };
+    if let Some(threads) = cli.threads {
+        config.num_threads = threads as usize;
+    }
+    let mut seats = cli.players;
+    seats.extend(std::iter::repeat_n(Seat::Random, cli.random));
+    let game_spec = GameSpec {
+        board: cli.board.0,
+        seats,
+        dead_cards: cli.dead.0,
+        game,
+    };
+    let hands: Vec<String> = game_spec
+        .seats
+        .iter()
+        .map(|seat| seat.to_string())
+        .collect();
+    let board = game_spec.board.iter().join(" ");
+    let dead_cards = game_spec.dead_cards.iter().join(" ");
+    let hi_lo = matches!(game_spec.game.variant().pot_split(), PotSplit::HiLo { .. });
+
+    let results = match poker::calculate_equity(game_spec, mode, Some(cli.simulations), &config) {
+        Ok(results) => results,
+        Err(error) => Cli::command()
+            .error(
+                ErrorKind::ValueValidation,
+                format!("invalid game: {}", error),
+            )
+            .exit(),
+    };
+
+    if !board.is_empty() {
+        println!("Board: {}", board);
+    }
+    if !dead_cards.is_empty() {
+        println!("Dead:  {}", dead_cards);
+    }
+    match results[0].seed {
+        Some(seed) => println!("{} simulated runouts, seed {}", results[0].trials, seed),
+        None => println!("{} runouts enumerated exactly", results[0].trials),
+    }
+    println!();
+    print_table(&hands, &results, hi_lo);
+}
+
+// Hi-lo games also get columns for the high and low parts of the equity and for how often each
+// player scoops or is quartered.
+fn print_table(hands: &[String], results: &[EquityResult], hi_lo: bool) {
+    let hand_width = hands
+        .iter()
+        .map(|hand| hand.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let columns = if hi_lo {
+        ["High", "Low", "Scoop", "Quarter"].as_slice()
+    } else {
+        ["Win", "Tie"].as_slice()
+    };
+    println!(
+        "{:>6}  {:<hand_width$}  {:>8}  {}  {:>18}",
+        "Player",
+        "Hand",
+        "Equity",
+        columns
+            .iter()
+            .map(|column| format!("{:>8}", column))
+            .join("  "),
+        "95% CI"
+    );
+    for (player, (hand, result)) in hands.iter().zip(results).enumerate() {
+        let (low, high) = result.confidence_interval;
+        let values = if hi_lo {
+            vec![
+                100. * result.high_equity,
+                100. * result.low_equity,
+                result.scoop_percentage,
+                result.quarter_percentage,
+            ]
+        } else {
+            vec![result.win_percentage, result.draw_percentage]
+        };
         println!(
-            "Equity for player {}: {} ({}% hands were wins, {}% draws)",
-            player, equity, win_percentage, draw_percentage
+            "{:>6}  {:<hand_width$}  {:>7.2}%  {}  {:>7.2}% - {:>6.2}%",
+            player + 1,
+            hand,
+            100. * result.equity,
+            values
+                .iter()
+                .map(|value| format!("{:>7.2}%", value))
+                .join("  "),
+            100. * low,
+            100. * high,
         );
     }
 }
+
+// Answers each line of JSON on stdin with a line of JSON on stdout, and returns the exit code:
+// non-zero if any request failed.
+fn answer_json_requests() -> i32 {
+    let mut stdout = io::stdout().lock();
+    let mut exit_code = 0;
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                eprintln!("error: couldn't read request: {}", error);
+                return 1;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = respond_to_json(&line);
+        if let EquityResponse::Error(_) = response {
+            exit_code = 1;
+        }
+        let written = serde_json::to_writer(&mut stdout, &response)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(stdout))
+            .and_then(|_| stdout.flush());
+        if let Err(error) = written {
+            eprintln!("error: couldn't write response: {}", error);
+            return 1;
+        }
+    }
+    exit_code
+}
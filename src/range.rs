@@ -0,0 +1,714 @@
+use crate::card_set::CardSet;
+use crate::cards::{parse_cards, parse_rank, rank_to_char, Card, Suit};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// Hand ranges in the usual shorthand, e.g. "QQ+, AKs, A5s-A2s, KQo, 76s+, AA:0.5, AhKh".
+//
+// Each comma-separated item is one of:
+//   - a pair ("QQ"), a suited hand ("AKs"), an offsuit hand ("AKo") or both ("AK")
+//   - any of those followed by "+": pairs go up to aces ("QQ+" = QQ, KK, AA), connectors move
+//     both cards up ("76s+" = 76s, 87s, ..., AKs) and other hands raise the kicker up to one
+//     below the top card ("A5s+" = A5s, A6s, ..., AKs)
+//   - a dash range between two hands of the same shape ("A5s-A2s", "QQ-99", "76s-54s")
+//   - a specific combo ("AhKh")
+// and may end with ":weight" giving the fraction of the time the hand is held (default 1).
+
+/// A two-card starting hand, with the higher card first.
+pub type Combo = (Card, Card);
+
+// The number of distinct two-card hands in a 52-card deck.
+const NUM_COMBOS: usize = 1326;
+
+/// A weighted set of two-card hands.
+#[derive(Clone)]
+pub struct Range {
+    combos: Vec<(Combo, f64)>,
+    // Where each combo is in `combos`, indexed by `combo_index`, so that looking one up doesn't
+    // mean scanning the whole list.
+    positions: Vec<Option<u16>>,
+}
+
+impl Default for Range {
+    fn default() -> Range {
+        Range {
+            combos: Vec::new(),
+            positions: vec![None; NUM_COMBOS],
+        }
+    }
+}
+
+// Two ranges are equal when they list the same combos, in the same order, with the same weights.
+impl PartialEq for Range {
+    fn eq(&self, other: &Range) -> bool {
+        self.combos == other.combos
+    }
+}
+
+impl fmt::Debug for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Range")
+            .field("combos", &self.combos)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeParseError {
+    InvalidHand(String),
+    MismatchedRange(String),
+    InvalidWeight(String),
+}
+
+impl fmt::Display for RangeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeParseError::InvalidHand(s) => write!(f, "invalid hand \"{}\"", s),
+            RangeParseError::MismatchedRange(s) => write!(f, "invalid hand range \"{}\"", s),
+            RangeParseError::InvalidWeight(s) => write!(f, "invalid weight \"{}\"", s),
+        }
+    }
+}
+
+impl Error for RangeParseError {}
+
+impl Range {
+    /// Every combo in the range with its weight.
+    pub fn combos(&self) -> &[(Combo, f64)] {
+        &self.combos
+    }
+
+    pub fn len(&self) -> usize {
+        self.combos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.combos.is_empty()
+    }
+
+    /// The weight of a combo, or 0 if it isn't in the range.
+    pub fn weight(&self, combo: Combo) -> f64 {
+        if combo.0 == combo.1 {
+            return 0.;
+        }
+        self.positions[combo_index(combo)].map_or(0., |i| self.combos[i as usize].1)
+    }
+
+    /// Adds a combo to the range, replacing its weight if it is already there. A weight of zero
+    /// removes it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if both cards of the combo are the same.
+    pub fn insert(&mut self, combo: Combo, weight: f64) {
+        assert_ne!(combo.0, combo.1, "A combo needs two different cards");
+        let combo = normalize(combo.0, combo.1);
+        let index = combo_index(combo);
+        match self.positions[index] {
+            Some(i) if weight == 0. => {
+                self.combos.remove(i as usize);
+                self.positions[index] = None;
+                for (j, &(later, _)) in self.combos.iter().enumerate().skip(i as usize) {
+                    self.positions[combo_index(later)] = Some(j as u16);
+                }
+            }
+            Some(i) => self.combos[i as usize].1 = weight,
+            None if weight == 0. => {}
+            None => {
+                self.positions[index] = Some(self.combos.len() as u16);
+                self.combos.push((combo, weight));
+            }
+        }
+    }
+
+    /// The range without the combos that use any of the given known cards.
+    pub fn remove_blocked(&self, known_cards: CardSet) -> Range {
+        let mut range = Range::default();
+        for &((c1, c2), weight) in &self.combos {
+            if !known_cards.contains(c1) && !known_cards.contains(c2) {
+                range.insert((c1, c2), weight);
+            }
+        }
+        range
+    }
+}
+
+// A distinct number below `NUM_COMBOS` for each two-card hand, whichever order its cards are in.
+fn combo_index((c1, c2): Combo) -> usize {
+    let (high, low) = (
+        c1.index().max(c2.index()) as usize,
+        c1.index().min(c2.index()) as usize,
+    );
+    high * (high - 1) / 2 + low
+}
+
+// The higher-ranked card first; for pairs, the higher suit first.
+fn normalize(c1: Card, c2: Card) -> Combo {
+    if (c1.rank(), c1.suit() as u8) >= (c2.rank(), c2.suit() as u8) {
+        (c1, c2)
+    } else {
+        (c2, c1)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Suitedness {
+    Suited,
+    Offsuit,
+    Any,
+}
+
+// A "hand class" such as "AKs" or "QQ": a pair of ranks (high first) plus suitedness.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct HandClass {
+    high: u8,
+    low: u8,
+    suitedness: Suitedness,
+}
+
+impl HandClass {
+    fn is_pair(&self) -> bool {
+        self.high == self.low
+    }
+
+    fn with_ranks(&self, high: u8, low: u8) -> HandClass {
+        HandClass {
+            high,
+            low,
+            suitedness: self.suitedness,
+        }
+    }
+
+    fn combos(&self) -> Vec<Combo> {
+        let mut combos = Vec::new();
+        for (i, &suit_1) in Suit::ALL.iter().enumerate() {
+            for (j, &suit_2) in Suit::ALL.iter().enumerate() {
+                let wanted = if self.is_pair() {
+                    i < j
+                } else {
+                    match self.suitedness {
+                        Suitedness::Suited => i == j,
+                        Suitedness::Offsuit => i != j,
+                        Suitedness::Any => true,
+                    }
+                };
+                if wanted {
+                    let c1 = Card::new(self.high, suit_1);
+                    let c2 = Card::new(self.low, suit_2);
+                    combos.push(normalize(c1, c2));
+                }
+            }
+        }
+        combos
+    }
+
+    // "QQ+", "76s+" and "A5s+" as described at the top of the file.
+    fn and_better(&self) -> Vec<HandClass> {
+        if self.is_pair() {
+            (self.high..=14).map(|r| self.with_ranks(r, r)).collect()
+        } else if self.low + 1 == self.high {
+            (0..=14 - self.high)
+                .map(|i| self.with_ranks(self.high + i, self.low + i))
+                .collect()
+        } else {
+            (self.low..self.high)
+                .map(|low| self.with_ranks(self.high, low))
+                .collect()
+        }
+    }
+
+    // Every class from `self` to `other` inclusive: either the kicker varies with the top card
+    // fixed, or both cards move together keeping the same gap.
+    fn through(&self, other: &HandClass) -> Option<Vec<HandClass>> {
+        if self.suitedness != other.suitedness || self.is_pair() != other.is_pair() {
+            return None;
+        }
+        if self.high == other.high {
+            let (from, to) = (self.low.min(other.low), self.low.max(other.low));
+            return Some(
+                (from..=to)
+                    .map(|low| self.with_ranks(self.high, low))
+                    .collect(),
+            );
+        }
+        let gap = self.high - self.low;
+        if other.high - other.low != gap {
+            return None;
+        }
+        let (from, to) = (self.low.min(other.low), self.low.max(other.low));
+        Some(
+            (from..=to)
+                .map(|low| self.with_ranks(low + gap, low))
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for HandClass {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<HandClass, ()> {
+        let mut chars = s.chars();
+        let rank_1 = chars.next().and_then(parse_rank).ok_or(())?;
+        let rank_2 = chars.next().and_then(parse_rank).ok_or(())?;
+        let suitedness = match chars.next() {
+            None => Suitedness::Any,
+            Some('s') if rank_1 != rank_2 => Suitedness::Suited,
+            Some('o') if rank_1 != rank_2 => Suitedness::Offsuit,
+            Some(_) => return Err(()),
+        };
+        if chars.next().is_some() {
+            return Err(());
+        }
+        Ok(HandClass {
+            high: rank_1.max(rank_2),
+            low: rank_1.min(rank_2),
+            suitedness,
+        })
+    }
+}
+
+fn parse_item(item: &str) -> Result<(Vec<Combo>, f64), RangeParseError> {
+    let (hand, weight) = match item.split_once(':') {
+        Some((hand, weight)) => {
+            let weight_error = || RangeParseError::InvalidWeight(weight.to_string());
+            let weight: f64 = weight.trim().parse().map_err(|_| weight_error())?;
+            if !(0. ..=1.).contains(&weight) {
+                return Err(weight_error());
+            }
+            (hand.trim(), weight)
+        }
+        None => (item, 1.),
+    };
+
+    if let Ok(cards) = parse_cards(hand) {
+        return match cards.as_slice() {
+            [c1, c2] if c1 != c2 => Ok((vec![normalize(*c1, *c2)], weight)),
+            _ => Err(RangeParseError::InvalidHand(hand.to_string())),
+        };
+    }
+
+    let parse_class = |s: &str| {
+        s.parse::<HandClass>()
+            .map_err(|_| RangeParseError::InvalidHand(s.to_string()))
+    };
+    let classes = if let Some((from, to)) = hand.split_once('-') {
+        parse_class(from)?
+            .through(&parse_class(to)?)
+            .ok_or_else(|| RangeParseError::MismatchedRange(hand.to_string()))?
+    } else if let Some(class) = hand.strip_suffix('+') {
+        parse_class(class)?.and_better()
+    } else {
+        vec![parse_class(hand)?]
+    };
+    let combos = classes.iter().flat_map(|class| class.combos()).collect();
+    Ok((combos, weight))
+}
+
+impl FromStr for Range {
+    type Err = RangeParseError;
+
+    /// Parses a comma-separated range. Later items override the weights of earlier ones.
+    fn from_str(s: &str) -> Result<Range, RangeParseError> {
+        let mut range = Range::default();
+        for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let (combos, weight) = parse_item(item)?;
+            for combo in combos {
+                range.insert(combo, weight);
+            }
+        }
+        Ok(range)
+    }
+}
+
+// Printing a range back out: complete hand classes (every combo present, all with the same
+// weight) are gathered into runs like "QQ+", "A5s-A2s" or "KQ", and whatever is left over is
+// listed combo by combo. Connectors and one-gappers that aren't part of a run with the same top
+// card are joined into runs like "76s+" or "T8s-86s" instead.
+
+const SUFFIXES: [(&str, Suitedness); 3] = [
+    ("", Suitedness::Any),
+    ("s", Suitedness::Suited),
+    ("o", Suitedness::Offsuit),
+];
+
+// A run of non-pair hand classes with the same suffix and weight, from its strongest class to its
+// weakest, as (high, low) ranks: either the kicker goes down with the top card fixed, or both
+// cards go down together.
+struct ClassRun {
+    suffix: usize,
+    first: (u8, u8),
+    last: (u8, u8),
+    weight: f64,
+}
+
+impl ClassRun {
+    fn is_single(&self) -> bool {
+        self.first == self.last
+    }
+
+    fn is_diagonal(&self) -> bool {
+        self.first.0 != self.last.0
+    }
+
+    fn to_item(&self) -> String {
+        let suffix = SUFFIXES[self.suffix].0;
+        let ((high, top), (bottom_high, bottom)) = (self.first, self.last);
+        let reaches_top = if self.is_diagonal() {
+            (high, top) == (14, 13)
+        } else {
+            top == high - 1
+        };
+        let item = if self.is_single() {
+            format_class(high, top, suffix)
+        } else if reaches_top {
+            format_class(bottom_high, bottom, &format!("{}+", suffix))
+        } else {
+            format!(
+                "{}-{}",
+                format_class(high, top, suffix),
+                format_class(bottom_high, bottom, suffix)
+            )
+        };
+        format_weight(item, self.weight)
+    }
+}
+
+// Joins connectors and one-gappers that are on their own into runs down the diagonal, each
+// keeping the place of its strongest class.
+fn join_diagonals(class_runs: Vec<ClassRun>) -> Vec<ClassRun> {
+    let mut joined: Vec<ClassRun> = Vec::new();
+    for run in class_runs {
+        let (high, low) = run.first;
+        let previous = joined.iter_mut().rev().find(|other| {
+            (other.is_single() || other.is_diagonal())
+                && other.suffix == run.suffix
+                && other.weight == run.weight
+                && other.last == (high + 1, low + 1)
+        });
+        match previous {
+            Some(previous) if run.is_single() && high - low <= 2 => previous.last = run.first,
+            _ => joined.push(run),
+        }
+    }
+    joined
+}
+
+fn class_weight(range: &Range, class: &HandClass) -> Option<f64> {
+    let mut weights = class.combos().into_iter().map(|combo| range.weight(combo));
+    let first = weights.next()?;
+    if first > 0. && weights.all(|weight| weight == first) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+fn format_class(high: u8, low: u8, suffix: &str) -> String {
+    format!("{}{}{}", rank_to_char(high), rank_to_char(low), suffix)
+}
+
+fn format_weight(item: String, weight: f64) -> String {
+    if weight == 1. {
+        item
+    } else {
+        format!("{}:{}", item, weight)
+    }
+}
+
+// Splits (rank, weight) pairs, highest rank first, into runs of consecutive ranks with the same
+// weight.
+fn runs(ranked_weights: &[(u8, f64)]) -> Vec<(u8, u8, f64)> {
+    let mut runs: Vec<(u8, u8, f64)> = Vec::new();
+    for &(rank, weight) in ranked_weights {
+        match runs.last_mut() {
+            Some((_, bottom, w)) if *bottom == rank + 1 && *w == weight => *bottom = rank,
+            _ => runs.push((rank, rank, weight)),
+        }
+    }
+    runs
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut items: Vec<String> = Vec::new();
+        let mut covered = Range::default();
+
+        // Pairs
+        let pairs: Vec<(u8, f64)> = (2..=14)
+            .rev()
+            .filter_map(|rank| {
+                let class = HandClass {
+                    high: rank,
+                    low: rank,
+                    suitedness: Suitedness::Any,
+                };
+                class_weight(self, &class).map(|weight| (rank, weight))
+            })
+            .collect();
+        for (top, bottom, weight) in runs(&pairs) {
+            let item = match (top, bottom) {
+                _ if top == bottom => format_class(top, top, ""),
+                (14, _) => format_class(bottom, bottom, "+"),
+                _ => format!(
+                    "{}-{}",
+                    format_class(top, top, ""),
+                    format_class(bottom, bottom, "")
+                ),
+            };
+            items.push(format_weight(item, weight));
+            for rank in bottom..=top {
+                let class = HandClass {
+                    high: rank,
+                    low: rank,
+                    suitedness: Suitedness::Any,
+                };
+                for combo in class.combos() {
+                    covered.insert(combo, weight);
+                }
+            }
+        }
+
+        // Non-pairs, by top card. Where the suited and offsuit versions of a hand have the same
+        // weight they are written together without a suffix.
+        let mut class_runs = Vec::new();
+        for high in (3..=14).rev() {
+            let mut by_suffix: [Vec<(u8, f64)>; 3] = Default::default();
+            for low in (2..high).rev() {
+                let class = |suitedness| HandClass {
+                    high,
+                    low,
+                    suitedness,
+                };
+                let suited = class_weight(self, &class(Suitedness::Suited));
+                let offsuit = class_weight(self, &class(Suitedness::Offsuit));
+                match (suited, offsuit) {
+                    (Some(s), Some(o)) if s == o => by_suffix[0].push((low, s)),
+                    _ => {
+                        if let Some(s) = suited {
+                            by_suffix[1].push((low, s));
+                        }
+                        if let Some(o) = offsuit {
+                            by_suffix[2].push((low, o));
+                        }
+                    }
+                }
+            }
+            for (suffix, ranked_weights) in by_suffix.iter().enumerate() {
+                for (top, bottom, weight) in runs(ranked_weights) {
+                    class_runs.push(ClassRun {
+                        suffix,
+                        first: (high, top),
+                        last: (high, bottom),
+                        weight,
+                    });
+                    for low in bottom..=top {
+                        let class = HandClass {
+                            high,
+                            low,
+                            suitedness: SUFFIXES[suffix].1,
+                        };
+                        for combo in class.combos() {
+                            covered.insert(combo, weight);
+                        }
+                    }
+                }
+            }
+        }
+
+        items.extend(join_diagonals(class_runs).iter().map(ClassRun::to_item));
+
+        // Anything else, combo by combo
+        let mut leftovers: Vec<(Combo, f64)> = self
+            .combos
+            .iter()
+            .filter(|(combo, _)| covered.weight(*combo) == 0.)
+            .copied()
+            .collect();
+        leftovers.sort_by_key(|((c1, c2), _)| {
+            std::cmp::Reverse((c1.rank(), c2.rank(), c1.suit() as u8, c2.suit() as u8))
+        });
+        for ((c1, c2), weight) in leftovers {
+            items.push(format_weight(format!("{}{}", c1, c2), weight));
+        }
+
+        write!(f, "{}", items.join(", "))
+    }
+}
+
+// Ranges are serialized as strings in range notation.
+impl Serialize for Range {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Range {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Range, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_range(s: &str) -> Range {
+        s.parse().unwrap()
+    }
+
+    fn same_combos(r1: &Range, r2: &Range) -> bool {
+        r1.len() == r2.len()
+            && r1
+                .combos()
+                .iter()
+                .all(|&(combo, weight)| r2.weight(combo) == weight)
+    }
+
+    #[test]
+    fn test_parse_combo_counts() {
+        assert_eq!(make_range("AA").len(), 6);
+        assert_eq!(make_range("AKs").len(), 4);
+        assert_eq!(make_range("AKo").len(), 12);
+        assert_eq!(make_range("AK").len(), 16);
+        assert_eq!(make_range("QQ+").len(), 18);
+        assert_eq!(make_range("A5s-A2s").len(), 16);
+        assert_eq!(make_range("A5s+").len(), 36);
+        assert_eq!(make_range("76s+").len(), 32);
+        assert_eq!(make_range("T8s+").len(), 8);
+        assert_eq!(make_range("QQ-99").len(), 24);
+        assert_eq!(make_range("99-QQ").len(), 24);
+        assert_eq!(make_range("76s-54s").len(), 12);
+        assert_eq!(make_range("AhKh").len(), 1);
+        assert_eq!(
+            make_range("QQ+, AKs, A5s-A2s, KQo, 76s+").len(),
+            18 + 4 + 16 + 12 + 28
+        );
+        assert!(make_range("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_weights() {
+        let range = make_range("AA:0.5, KK");
+        let aces = ("As".parse().unwrap(), "Ah".parse().unwrap());
+        let kings = ("Kd".parse().unwrap(), "Kc".parse().unwrap());
+        assert_eq!(range.weight(aces), 0.5);
+        assert_eq!(range.weight(kings), 1.);
+        assert_eq!(
+            range.weight(("Qs".parse().unwrap(), "Qh".parse().unwrap())),
+            0.
+        );
+
+        // later items override earlier ones
+        let range = make_range("QQ+, AA:0.25");
+        assert_eq!(range.weight(aces), 0.25);
+        assert_eq!(range.weight(kings), 1.);
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let deck = Card::create_deck();
+        let mut range = Range::default();
+        for (i, &card) in deck.iter().enumerate() {
+            for &other in &deck[..i] {
+                range.insert((card, other), 1.);
+            }
+        }
+        assert_eq!(range.len(), NUM_COMBOS);
+        let mut indexes: Vec<usize> = range
+            .combos()
+            .iter()
+            .map(|&(c, _)| combo_index(c))
+            .collect();
+        indexes.sort();
+        assert!(indexes.iter().copied().eq(0..NUM_COMBOS));
+
+        // removing combos from the middle keeps every other combo's weight
+        for &card in &deck[..10] {
+            for &other in &deck[10..] {
+                range.insert((other, card), 0.);
+            }
+        }
+        range.insert((deck[20], deck[30]), 0.5);
+        assert_eq!(range.len(), 45 + 42 * 41 / 2);
+        assert_eq!(range.weight((deck[30], deck[20])), 0.5);
+        assert_eq!(range.weight((deck[3], deck[40])), 0.);
+        assert_eq!(range.weight((deck[3], deck[4])), 1.);
+        assert_eq!(range.weight((deck[50], deck[51])), 1.);
+        assert!(range
+            .combos()
+            .iter()
+            .all(|&(combo, weight)| range.weight(combo) == weight));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let invalid_hand = |s: &str| Err(RangeParseError::InvalidHand(s.to_string()));
+        assert_eq!("AAs".parse::<Range>(), invalid_hand("AAs"));
+        assert_eq!("AX".parse::<Range>(), invalid_hand("AX"));
+        assert_eq!("QQ, AKx".parse::<Range>(), invalid_hand("AKx"));
+        assert_eq!("AhAh".parse::<Range>(), invalid_hand("AhAh"));
+        assert_eq!(
+            "A5s-K2s".parse::<Range>(),
+            Err(RangeParseError::MismatchedRange(String::from("A5s-K2s")))
+        );
+        assert_eq!(
+            "AKs-QQ".parse::<Range>(),
+            Err(RangeParseError::MismatchedRange(String::from("AKs-QQ")))
+        );
+        assert_eq!(
+            "AA:2".parse::<Range>(),
+            Err(RangeParseError::InvalidWeight(String::from("2")))
+        );
+        assert_eq!(
+            "AA:x".parse::<Range>(),
+            Err(RangeParseError::InvalidWeight(String::from("x")))
+        );
+    }
+
+    #[test]
+    fn test_remove_blocked() {
+        let range = make_range("AA, AKs");
+        let known: CardSet = parse_cards("As Kh").unwrap().into_iter().collect();
+        let unblocked = range.remove_blocked(known);
+        // three aces left make 3 pairs, and AcKc and AdKd are the only suited AK left
+        assert_eq!(unblocked.len(), 3 + 2);
+        assert_eq!(unblocked.to_string(), "AdAh, AdAc, AhAc, AdKd, AcKc");
+    }
+
+    #[test]
+    fn test_display_canonical() {
+        let cases = [
+            ("QQ+, AKs, A5s-A2s, KQo", "QQ+, AKs, A5s-A2s, KQo"),
+            ("AKs, AKo", "AK"),
+            ("KK-QQ, AA", "QQ+"),
+            ("JJ, 99", "JJ, 99"),
+            ("K9s+", "K9s+"),
+            ("A2s, A3s, A4s", "A4s-A2s"),
+            ("76s+", "76s+"),
+            ("76s, 65s, 54s", "76s-54s"),
+            ("AKo, KQo:0.5, QJo:0.5", "AKo, KQo-QJo:0.5"),
+            ("T8s, 97s, 86s", "T8s-86s"),
+            ("AQs-64s", "AQs-64s"),
+            ("T9, 98, 87", "T9-87"),
+            // Runs with the same top card come first
+            ("A2s+, K2s+, Q2s+", "A2s+, K2s+, Q2s+"),
+            ("AQs+, KQs", "AQs+, KQs"),
+            ("AA:0.5, KK", "AA:0.5, KK"),
+            ("AhKh, AKo", "AKo, AhKh"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(make_range(input).to_string(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let range = make_range("QQ+:0.75, AKs, A5s-A2s, KQo, 76s+, T8s-75s, T9o:0.5, 2h2c, 8d7c");
+        assert!(same_combos(&make_range(&range.to_string()), &range));
+    }
+}
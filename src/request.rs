@@ -0,0 +1,266 @@
+use crate::cards::Card;
+use crate::equity::{calculate_equity, EquityConfig, EquityMode, EquityResult};
+use crate::game::{GameSpec, GameSpecError, GameType, Seat};
+use serde::{Deserialize, Serialize};
+
+// Equity requests and responses as JSON, for running calculations from other programs.
+//
+// A request names each player's hand, and optionally the board, any dead cards, the game and
+// settings for the calculation. Cards are strings like "As", and each player's hand is a string
+// giving what is known about it, as parsed by `Seat`: some or all of the cards, a stud player's
+// down and up cards split by a slash, a range in range notation or "random". So a request looks
+// like:
+//
+//   {
+//     "players": ["AsKd", "QQ+, AKs"],
+//     "board": ["Qs", "Kd", "Jc"],
+//     "dead_cards": ["2c"],
+//     "game": "holdem",
+//     "settings": {"mode": "monte_carlo", "simulations": 100000, "seed": 42, "threads": 4}
+//   }
+//
+// Only "players" is required. The game is one of "holdem" (the default), "omaha", "omaha5",
+// "omaha6", "omaha_hi_lo", "short_deck", "short_deck_trips_beat_straight", "stud", "stud_hi_lo"
+// or "razz". Stud games have no board. The settings are all optional: "mode" is one of "auto"
+// (the default), "exact" or "monte_carlo", and the rest default as they do for
+// `calculate_equity`, except that "threads" can be at most `MAX_REQUEST_THREADS`. Unknown fields
+// are rejected so that typos don't go unnoticed.
+//
+// The response is either {"results": [...]} with one `EquityResult` per player, in order, or
+// {"error": "..."} saying what was wrong with the request.
+
+/// The most threads a request can ask to be run on, so that one request can't make the process
+/// spawn as many as it likes.
+pub const MAX_REQUEST_THREADS: usize = 256;
+
+/// One equity calculation to run, in the JSON request format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EquityRequest {
+    pub players: Vec<Seat>,
+    #[serde(default)]
+    pub board: Vec<Card>,
+    #[serde(default)]
+    pub dead_cards: Vec<Card>,
+    #[serde(default)]
+    pub game: GameType,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+/// How to run an `EquityRequest`. Anything left out takes its usual default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub mode: EquityMode,
+    pub simulations: Option<u64>,
+    pub seed: Option<u64>,
+    pub threads: Option<usize>,
+}
+
+/// The answer to an `EquityRequest`, in the JSON response format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EquityResponse {
+    Results(Vec<EquityResult>),
+    Error(String),
+}
+
+impl EquityRequest {
+    pub fn game_spec(&self) -> GameSpec {
+        GameSpec {
+            board: self.board.clone(),
+            seats: self.players.clone(),
+            dead_cards: self.dead_cards.clone(),
+            game: self.game,
+        }
+    }
+
+    pub fn config(&self) -> EquityConfig {
+        let mut config = EquityConfig {
+            seed: self.settings.seed,
+            ..EquityConfig::default()
+        };
+        if let Some(threads) = self.settings.threads {
+            config.num_threads = threads;
+        }
+        config
+    }
+
+    /// Runs the calculation. Asking for more than `MAX_REQUEST_THREADS` threads is an error.
+    pub fn run(&self) -> Result<Vec<EquityResult>, GameSpecError> {
+        match self.settings.threads {
+            Some(threads) if threads > MAX_REQUEST_THREADS => {
+                return Err(GameSpecError::TooManyThreads(threads))
+            }
+            _ => {}
+        }
+        calculate_equity(
+            self.game_spec(),
+            self.settings.mode,
+            self.settings.simulations,
+            &self.config(),
+        )
+    }
+}
+
+/// Parses a single JSON request and runs it. Anything that goes wrong, whether the JSON is bad
+/// or the game is impossible, becomes an error response.
+pub fn respond_to_json(json: &str) -> EquityResponse {
+    let request: EquityRequest = match serde_json::from_str(json) {
+        Ok(request) => request,
+        Err(error) => return EquityResponse::Error(format!("invalid request: {}", error)),
+    };
+    match request.run() {
+        Ok(results) => EquityResponse::Results(results),
+        Err(
+            error @ (GameSpecError::NoSimulations
+            | GameSpecError::InvalidPrecision
+            | GameSpecError::TooManyThreads(_)),
+        ) => EquityResponse::Error(format!("invalid settings: {}", error)),
+        Err(error) => EquityResponse::Error(format!("invalid game: {}", error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+
+    #[test]
+    fn test_parse_request() {
+        let json = r#"{
+            "players": ["AsKd", "QQ+, AKs"],
+            "board": ["Qs", "Kd", "Jc"],
+            "dead_cards": ["2c"],
+            "settings": {"mode": "monte_carlo", "simulations": 1000, "seed": 42, "threads": 4}
+        }"#;
+        let request: EquityRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.players[0], "AsKd".parse().unwrap());
+        assert_eq!(request.players[1], "QQ+, AKs".parse().unwrap());
+        assert_eq!(request.board, parse_cards("Qs Kd Jc").unwrap());
+        assert_eq!(request.dead_cards, parse_cards("2c").unwrap());
+        assert_eq!(request.settings.mode, EquityMode::MonteCarlo);
+        assert_eq!(request.config().num_threads, 4);
+        assert_eq!(request.config().seed, Some(42));
+    }
+
+    #[test]
+    fn test_parse_request_defaults() {
+        let request: EquityRequest = serde_json::from_str(r#"{"players": ["AA", "KK"]}"#).unwrap();
+        assert!(request.board.is_empty());
+        assert!(request.dead_cards.is_empty());
+        assert_eq!(request.game, GameType::Holdem);
+        assert_eq!(request.settings, Settings::default());
+        assert_eq!(request.settings.mode, EquityMode::Auto);
+    }
+
+    #[test]
+    fn test_parse_request_errors() {
+        for json in [
+            r#"{"board": ["Qs"]}"#,
+            r#"{"players": ["AA", "KK"], "bored": ["Qs"]}"#,
+            r#"{"players": ["AA", "KK"], "board": ["Qx"]}"#,
+            r#"{"players": ["AA", "ZZ"]}"#,
+            r#"{"players": ["AA", "KK"], "settings": {"mode": "guess"}}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<EquityRequest>(json).is_err(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn test_respond_to_json() {
+        let json = r#"{"players": ["QhQd", "AcAs"], "board": ["Qs", "Kd", "Jc", "Tc"]}"#;
+        let EquityResponse::Results(results) = respond_to_json(json) else {
+            panic!("Expected results");
+        };
+        assert_eq!(results[0].trials, 44);
+        assert_eq!(results[0].seed, None);
+
+        let response = respond_to_json(r#"{"players": ["QhQd", "QhQs"]}"#);
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            json,
+            r#"{"error":"invalid game: card Qh appears more than once"}"#
+        );
+        assert!(matches!(
+            respond_to_json("not json"),
+            EquityResponse::Error(_)
+        ));
+
+        let json = r#"{"players": ["AA", "KK"], "settings": {"simulations": 0}}"#;
+        assert_eq!(
+            respond_to_json(json),
+            EquityResponse::Error(
+                "invalid settings: at least one simulation is needed".to_string()
+            )
+        );
+
+        let json = r#"{"players": ["AA", "KK"], "settings": {"threads": 100000}}"#;
+        assert_eq!(
+            respond_to_json(json),
+            EquityResponse::Error(
+                "invalid settings: 100000 threads asked for, at most 256 allowed".to_string()
+            )
+        );
+        let json = r#"{"players": ["AA", "KK"], "settings": {"threads": 256, "simulations": 256}}"#;
+        assert!(matches!(respond_to_json(json), EquityResponse::Results(_)));
+    }
+
+    #[test]
+    fn test_respond_to_omaha_json() {
+        let json = r#"{
+            "players": ["AhAd2c3d", "KcKh7s8s"],
+            "board": ["As", "Ks", "2h", "3h"],
+            "game": "plo4"
+        }"#;
+        let EquityResponse::Results(results) = respond_to_json(json) else {
+            panic!("Expected results");
+        };
+        assert_eq!(results[0].trials, 40);
+
+        let response = respond_to_json(r#"{"players": ["AA", "KK"], "game": "omaha"}"#);
+        assert_eq!(
+            response,
+            EquityResponse::Error(
+                "invalid game: player 0 has a range, but ranges are only supported in hold'em \
+                 games, including short deck"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_serialize_results() {
+        let json = r#"{"players": ["QhQd", "AcAs"], "board": ["Qs", "Kd", "Jc", "Tc"]}"#;
+        let response = respond_to_json(json);
+        let value = serde_json::to_value(&response).unwrap();
+        assert_eq!(value["results"][0]["wins"], 10);
+        assert_eq!(value["results"][1]["equity"], 0.75);
+        assert_eq!(value["results"][1]["seed"], serde_json::Value::Null);
+        let round_trip: EquityResponse = serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip, response);
+    }
+
+    #[test]
+    fn test_serialize_one_trial_results() {
+        let json = r#"{
+            "players": ["QhQd", "AA, KK"],
+            "settings": {"mode": "monte_carlo", "simulations": 1, "seed": 42, "threads": 1}
+        }"#;
+        let response = respond_to_json(json);
+        let EquityResponse::Results(results) = &response else {
+            panic!("Expected results");
+        };
+        assert_eq!(results[0].std_error, None);
+        assert_eq!(results[0].confidence_interval, (0., 1.));
+        assert_eq!(results[1].combos[0].1.std_error, None);
+        let json = serde_json::to_string(&response).unwrap();
+        let round_trip: EquityResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip, response);
+    }
+}
@@ -0,0 +1,417 @@
+use crate::card_set::CardSet;
+use crate::evaluator::{evaluate_with_ranking, HandStrength};
+use crate::hands::HandRanking;
+use crate::lowball::{evaluate_low, LowRules, LowStrength};
+use itertools::Either;
+use std::fmt;
+
+// The number of cards in a poker hand.
+pub(crate) const HAND_SIZE: usize = 5;
+
+// The number of community cards in flop games like hold'em and Omaha.
+const FLOP_GAME_BOARD_SIZE: usize = 5;
+
+/// Who wins the pot at showdown. Low hands are ranked by the game's `Variant::low_rules`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PotSplit {
+    /// The best high hand takes it all.
+    High,
+    /// Half goes to the best high hand and half to the best low with no card above
+    /// `qualifier`, or all of it to high if nobody has a low.
+    HiLo { qualifier: u8 },
+    /// The best low hand takes it all.
+    Low,
+}
+
+/// The rules of a kind of poker, as far as working out equity goes: which cards are dealt and
+/// where, how a player makes their best hand, and who wins the pot. `Game`, and so the
+/// simulations and enumerations built on it, only deal and score hands through this trait.
+///
+/// Implementations only have to describe the deal. The provided methods make and score hands
+/// from that description. A game that makes its high hands some other way can override
+/// `best_hand` and `best_hand_cards` together, which between them are all that `Game` uses to
+/// find high hands. A new game is added by implementing this, or reusing one of the
+/// implementations here, and giving it a `GameType`.
+pub trait Variant: fmt::Debug + Send + Sync {
+    /// The cards the game is dealt from.
+    fn deck(&self) -> CardSet;
+
+    /// The number of cards each player is dealt for themselves, face down or face up.
+    fn num_hole_cards(&self) -> usize;
+
+    /// The number of community cards dealt, which can be none at all.
+    fn board_size(&self) -> usize;
+
+    /// How many hole cards must go into a player's hand, with the rest from the board, or None
+    /// if they can use any number.
+    fn hole_cards_used(&self) -> Option<usize>;
+
+    /// How hands are ranked against each other for high.
+    fn ranking(&self) -> HandRanking;
+
+    fn pot_split(&self) -> PotSplit;
+
+    /// How hands are ranked for low, in games with a low half or played for low alone.
+    fn low_rules(&self) -> LowRules {
+        LowRules::AceToFive
+    }
+
+    /// Scores the best high hand a player can make from their hole cards and the complete board.
+    fn best_hand(&self, hole_cards: CardSet, board: CardSet) -> HandStrength {
+        let ranking = self.ranking();
+        match self.hole_cards_used() {
+            None => evaluate_with_ranking(board.union(hole_cards), ranking),
+            Some(used) => allowed_hands(hole_cards, board, used)
+                .map(|hand| evaluate_with_ranking(hand, ranking))
+                .max()
+                .unwrap(),
+        }
+    }
+
+    /// The five cards that make the hand `best_hand` scores. When several hands are worth the
+    /// same, any one of them.
+    fn best_hand_cards(&self, hole_cards: CardSet, board: CardSet) -> CardSet {
+        let ranking = self.ranking();
+        let hands = match self.hole_cards_used() {
+            None => Either::Left(board.union(hole_cards).combinations(HAND_SIZE)),
+            Some(used) => Either::Right(allowed_hands(hole_cards, board, used)),
+        };
+        hands
+            .max_by_key(|&hand| evaluate_with_ranking(hand, ranking))
+            .unwrap()
+    }
+
+    /// Scores the best low hand a player can make from their hole cards and the complete board,
+    /// or None if the game isn't played for low or they don't have a low that qualifies.
+    fn best_low(&self, hole_cards: CardSet, board: CardSet) -> Option<LowStrength> {
+        let qualifier = match self.pot_split() {
+            PotSplit::High => return None,
+            PotSplit::HiLo { qualifier } => Some(qualifier),
+            PotSplit::Low => None,
+        };
+        // A qualifying low hand is never paired (or, under deuce-to-seven rules, a straight or a
+        // flush), so its best low is always the qualifying one if there is one at all.
+        let rules = self.low_rules();
+        let low = match self.hole_cards_used() {
+            None => evaluate_low(board.union(hole_cards), rules),
+            Some(used) => {
+                // Without enough different low cards on the board nobody can make a low
+                if qualifier.is_some_and(|qualifier| {
+                    num_low_ranks(board, qualifier, rules) < HAND_SIZE - used
+                }) {
+                    return None;
+                }
+                allowed_hands(hole_cards, board, used)
+                    .map(|hand| evaluate_low(hand, rules))
+                    .max()
+                    .unwrap()
+            }
+        };
+        match qualifier {
+            Some(qualifier) => Some(low).filter(|low| low.is_at_most(qualifier)),
+            None => Some(low),
+        }
+    }
+
+    /// How many five-card hands have to be scored to find a player's best hand once the board
+    /// is complete, counting each hand twice in hi-lo games where it's scored for low as well.
+    fn hands_per_player(&self) -> u64 {
+        let hands = match self.hole_cards_used() {
+            None => 1,
+            Some(used) => {
+                num_combinations(self.num_hole_cards(), used)
+                    * num_combinations(self.board_size(), HAND_SIZE - used)
+            }
+        };
+        match self.pot_split() {
+            PotSplit::HiLo { .. } => 2 * hands,
+            PotSplit::High | PotSplit::Low => hands,
+        }
+    }
+}
+
+/// Hold'em: two hole cards and a five-card board, of which any five cards play. Short-deck
+/// hold'em is hold'em with a short-deck ranking, which also deals from the short deck.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Holdem {
+    pub ranking: HandRanking,
+}
+
+impl Variant for Holdem {
+    fn deck(&self) -> CardSet {
+        if self.ranking.is_short_deck() {
+            CardSet::short_deck()
+        } else {
+            CardSet::full_deck()
+        }
+    }
+
+    fn num_hole_cards(&self) -> usize {
+        2
+    }
+
+    fn board_size(&self) -> usize {
+        FLOP_GAME_BOARD_SIZE
+    }
+
+    fn hole_cards_used(&self) -> Option<usize> {
+        None
+    }
+
+    fn ranking(&self) -> HandRanking {
+        self.ranking
+    }
+
+    fn pot_split(&self) -> PotSplit {
+        PotSplit::High
+    }
+}
+
+/// Omaha: four or more hole cards and a five-card board, where a hand is made from exactly two
+/// hole cards and three board cards, for high or split hi-lo.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Omaha {
+    pub num_hole_cards: usize,
+    pub pot_split: PotSplit,
+}
+
+impl Variant for Omaha {
+    fn deck(&self) -> CardSet {
+        CardSet::full_deck()
+    }
+
+    fn num_hole_cards(&self) -> usize {
+        self.num_hole_cards
+    }
+
+    fn board_size(&self) -> usize {
+        FLOP_GAME_BOARD_SIZE
+    }
+
+    fn hole_cards_used(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn ranking(&self) -> HandRanking {
+        HandRanking::Standard
+    }
+
+    fn pot_split(&self) -> PotSplit {
+        self.pot_split
+    }
+}
+
+/// Seven-card stud: no board, and the best five of each player's own seven cards play, for
+/// high, split hi-lo, or for low as in razz.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Stud {
+    pub pot_split: PotSplit,
+}
+
+impl Variant for Stud {
+    fn deck(&self) -> CardSet {
+        CardSet::full_deck()
+    }
+
+    fn num_hole_cards(&self) -> usize {
+        7
+    }
+
+    fn board_size(&self) -> usize {
+        0
+    }
+
+    fn hole_cards_used(&self) -> Option<usize> {
+        None
+    }
+
+    fn ranking(&self) -> HandRanking {
+        HandRanking::Standard
+    }
+
+    fn pot_split(&self) -> PotSplit {
+        self.pot_split
+    }
+}
+
+// Every five-card hand made from exactly `used` hole cards and the rest from the board.
+fn allowed_hands(
+    hole_cards: CardSet,
+    board: CardSet,
+    used: usize,
+) -> impl Iterator<Item = CardSet> {
+    hole_cards.combinations(used).flat_map(move |hole_cards| {
+        board
+            .combinations(HAND_SIZE - used)
+            .map(move |board_cards| hole_cards.union(board_cards))
+    })
+}
+
+fn num_combinations(n: usize, k: usize) -> u64 {
+    (0..k as u64).fold(1, |acc, i| acc * (n as u64 - i) / (i + 1))
+}
+
+// The number of different ranks no higher than `qualifier` among the cards, counting aces low
+// under ace-to-five rules.
+fn num_low_ranks(cards: CardSet, qualifier: u8, rules: LowRules) -> usize {
+    let ace_low = rules == LowRules::AceToFive;
+    let ranks = cards.iter().fold(0u16, |ranks, card| {
+        ranks
+            | 1 << if card.rank() == 14 && ace_low {
+                1
+            } else {
+                card.rank()
+            }
+    });
+    (ranks & ((2 << qualifier) - 1)).count_ones() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+    use crate::game::GameType;
+    use crate::hands::HandType;
+
+    fn make_set(cards_str: &str) -> CardSet {
+        parse_cards(cards_str).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn test_game_type_variants() {
+        let deals: Vec<_> = [
+            GameType::Holdem,
+            GameType::Omaha6,
+            GameType::OmahaHiLo,
+            GameType::ShortDeck,
+            GameType::Razz,
+        ]
+        .iter()
+        .map(|game| {
+            let variant = game.variant();
+            (
+                variant.deck().count(),
+                variant.num_hole_cards(),
+                variant.board_size(),
+                variant.hole_cards_used(),
+                variant.pot_split(),
+            )
+        })
+        .collect();
+        assert_eq!(
+            deals,
+            [
+                (52, 2, 5, None, PotSplit::High),
+                (52, 6, 5, Some(2), PotSplit::High),
+                (52, 4, 5, Some(2), PotSplit::HiLo { qualifier: 8 }),
+                (36, 2, 5, None, PotSplit::High),
+                (52, 7, 0, None, PotSplit::Low),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_provided_methods_for_new_variant() {
+        // Five-card Omaha hi-lo ("Big O") needs nothing more than a description of its deal
+        let big_o = Omaha {
+            num_hole_cards: 5,
+            pot_split: PotSplit::HiLo { qualifier: 8 },
+        };
+        let hole_cards = make_set("Ah 2d Kc Kd Qs");
+        let board = make_set("3c 4d 8h Kh Js");
+        assert_eq!(
+            big_o.best_hand(hole_cards, board).hand_type(),
+            HandType::ThreeOfAKind
+        );
+        let low = big_o.best_low(hole_cards, board).unwrap();
+        assert_eq!(low.describe(), "8-4 low (8-4-3-2-A)");
+        assert_eq!(big_o.hands_per_player(), 200);
+
+        // One low card in the hand isn't enough, however many are on the board
+        let hole_cards = make_set("Ah Qd Kc Kd Qs");
+        assert_eq!(big_o.best_low(hole_cards, board), None);
+        let holdem = Holdem {
+            ranking: HandRanking::Standard,
+        };
+        assert_eq!(holdem.best_low(hole_cards, board), None);
+    }
+
+    #[test]
+    fn test_best_hand_cards() {
+        let board = make_set("Ah Kh 7h 7c 2s");
+        for (game, hole_cards, best_cards) in [
+            (GameType::Holdem, "Qh 3h", "Ah Kh Qh 7h 3h"),
+            (GameType::Omaha, "Qh 3d 7d 2c", "7d 7h 7c 2c 2s"),
+            (GameType::Omaha, "Qh Jh Td 9d", "Ah Kh Qh Jh 7h"),
+        ] {
+            let variant = game.variant();
+            let hole_cards = make_set(hole_cards);
+            let cards = variant.best_hand_cards(hole_cards, board);
+            assert_eq!(cards, make_set(best_cards));
+            assert_eq!(
+                evaluate_with_ranking(cards, variant.ranking()),
+                variant.best_hand(hole_cards, board)
+            );
+        }
+    }
+
+    #[test]
+    fn test_low_rules() {
+        // Razz played for the best deuce-to-seven low, where the ace is high
+        #[derive(Debug)]
+        struct DeuceToSevenRazz;
+
+        impl Variant for DeuceToSevenRazz {
+            fn deck(&self) -> CardSet {
+                CardSet::full_deck()
+            }
+
+            fn num_hole_cards(&self) -> usize {
+                7
+            }
+
+            fn board_size(&self) -> usize {
+                0
+            }
+
+            fn hole_cards_used(&self) -> Option<usize> {
+                None
+            }
+
+            fn ranking(&self) -> HandRanking {
+                HandRanking::Standard
+            }
+
+            fn pot_split(&self) -> PotSplit {
+                PotSplit::Low
+            }
+
+            fn low_rules(&self) -> LowRules {
+                LowRules::DeuceToSeven
+            }
+        }
+
+        let cards = make_set("As 2d 3c 4h 5s Kd Kh");
+        assert_eq!(
+            DeuceToSevenRazz.best_low(cards, CardSet::empty()),
+            Some(evaluate_low(
+                make_set("Kd 5s 4h 3c 2d"),
+                LowRules::DeuceToSeven
+            ))
+        );
+        let razz = GameType::Razz.variant();
+        assert_eq!(razz.low_rules(), LowRules::AceToFive);
+        assert_eq!(
+            razz.best_low(cards, CardSet::empty()),
+            Some(evaluate_low(
+                make_set("As 2d 3c 4h 5s"),
+                LowRules::AceToFive
+            ))
+        );
+
+        // An ace on the board only counts towards an eight-or-better low when aces are low
+        let board = make_set("Ac 2h 7d Ks Qs");
+        assert_eq!(num_low_ranks(board, 8, LowRules::AceToFive), 3);
+        assert_eq!(num_low_ranks(board, 8, LowRules::DeuceToSeven), 2);
+    }
+}